@@ -0,0 +1,219 @@
+use crate::coordinate::IndexingCoordinate;
+
+/// How a generation's new values are applied
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Update {
+    /// Every cell sees its neighbours as they were at the start of the generation
+    Synchronous,
+    /// Cells are updated in row-major order, and later cells see earlier ones' new values
+    InPlace,
+}
+
+/// Which cells count as neighbours
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// Up, down, left and right
+    Orthogonal,
+    /// The eight surrounding cells, including diagonals
+    Moore,
+}
+
+/// How a run finished
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    /// The number of generations in which at least one cell changed
+    pub generations: usize,
+    /// Whether the grid stopped changing, rather than hitting the generation limit
+    pub fixed_point: bool,
+}
+
+/// A cellular automaton over a grid, where `rule` maps a cell and the values of its
+/// neighbours to the cell's next value.
+///
+/// After the first generation only cells next to a change are revisited, so a rule that
+/// settles down locally, like erosion, doesn't keep paying for the whole grid.
+pub struct Automaton<T, F> {
+    grid: Vec<Vec<T>>,
+    rule: F,
+    update: Update,
+    neighbourhood: Neighbourhood,
+    generation: usize,
+    // The cells to visit next generation, or None for all of them
+    frontier: Option<Vec<IndexingCoordinate>>,
+    // The step in which each cell was last added to the frontier, so it's only added once
+    // without clearing a whole grid of flags every step
+    queued: Vec<Vec<usize>>,
+    steps: usize,
+}
+
+impl<T, F> Automaton<T, F>
+where
+    T: Clone + PartialEq,
+    F: FnMut(&T, &[T]) -> T,
+{
+    pub fn new(grid: Vec<Vec<T>>, neighbourhood: Neighbourhood, rule: F) -> Automaton<T, F> {
+        let queued = grid.iter().map(|row| vec![0; row.len()]).collect();
+        Automaton {
+            grid,
+            rule,
+            update: Update::Synchronous,
+            neighbourhood,
+            generation: 0,
+            frontier: None,
+            queued,
+            steps: 0,
+        }
+    }
+
+    pub fn with_update(mut self, update: Update) -> Automaton<T, F> {
+        self.update = update;
+        self
+    }
+
+    pub fn grid(&self) -> &[Vec<T>] {
+        &self.grid
+    }
+
+    pub fn into_grid(self) -> Vec<Vec<T>> {
+        self.grid
+    }
+
+    /// The number of generations so far that changed something
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Run one generation, returning how many cells changed
+    pub fn step(&mut self) -> usize {
+        let rows = self.grid.len();
+        let cols = self.grid.first().map_or(0, |row| row.len());
+        let cells = self.frontier.take().unwrap_or_else(|| {
+            (0..rows)
+                .flat_map(|row| (0..cols).map(move |col| IndexingCoordinate::new(row, col)))
+                .collect()
+        });
+        let mut neighbours = vec![];
+        let mut changed = vec![];
+        for cell in cells {
+            neighbours.clear();
+            neighbours.extend(
+                self.neighbours(cell)
+                    .into_iter()
+                    .map(|n| self.grid[n.row][n.col].clone()),
+            );
+            let current = &self.grid[cell.row][cell.col];
+            let next = (self.rule)(current, &neighbours);
+            if next != *current {
+                if self.update == Update::InPlace {
+                    self.grid[cell.row][cell.col] = next.clone();
+                }
+                changed.push((cell, next));
+            }
+        }
+        // Only a changed cell or its neighbours can change next time
+        self.steps += 1;
+        let mut frontier = vec![];
+        for (cell, value) in &changed {
+            if self.update == Update::Synchronous {
+                self.grid[cell.row][cell.col] = value.clone();
+            }
+            for next in self.neighbours(*cell).into_iter().chain([*cell]) {
+                let stamp = &mut self.queued[next.row][next.col];
+                if *stamp != self.steps {
+                    *stamp = self.steps;
+                    frontier.push(next);
+                }
+            }
+        }
+        frontier.sort_by_key(|c| (c.row, c.col));
+        self.frontier = Some(frontier);
+        if !changed.is_empty() {
+            self.generation += 1;
+        }
+        changed.len()
+    }
+
+    /// Step until nothing changes, or until `limit` generations have run in total
+    pub fn run(&mut self, limit: Option<usize>) -> Outcome {
+        loop {
+            if limit.is_some_and(|limit| self.generation >= limit) {
+                return Outcome {
+                    generations: self.generation,
+                    fixed_point: false,
+                };
+            }
+            if self.step() == 0 {
+                return Outcome {
+                    generations: self.generation,
+                    fixed_point: true,
+                };
+            }
+        }
+    }
+
+    fn neighbours(&self, cell: IndexingCoordinate) -> Vec<IndexingCoordinate> {
+        let rows = self.grid.len();
+        let cols = self.grid[0].len();
+        match self.neighbourhood {
+            Neighbourhood::Orthogonal => cell.get_adjacent_points(rows, cols),
+            Neighbourhood::Moore => cell.get_adjacent_points_diagonal(rows, cols),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn life(cell: &bool, neighbours: &[bool]) -> bool {
+        let alive = neighbours.iter().filter(|n| **n).count();
+        alive == 3 || (*cell && alive == 2)
+    }
+
+    fn parse(rows: &[&str]) -> Vec<Vec<bool>> {
+        rows.iter()
+            .map(|row| row.chars().map(|c| c == '#').collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_life() -> color_eyre::Result<()> {
+        let blinker = parse(&[".....", "..#..", "..#..", "..#..", "....."]);
+        let mut automaton = Automaton::new(blinker.clone(), Neighbourhood::Moore, life);
+        assert_eq!(automaton.step(), 4);
+        assert_eq!(
+            automaton.grid(),
+            parse(&[".....", ".....", ".###.", ".....", "....."])
+        );
+        // It never settles, so the limit stops it
+        let outcome = automaton.run(Some(4));
+        assert_eq!(
+            outcome,
+            Outcome {
+                generations: 4,
+                fixed_point: false
+            }
+        );
+        assert_eq!(automaton.into_grid(), blinker);
+        let block = parse(&["....", ".##.", ".##.", "...."]);
+        let mut automaton = Automaton::new(block, Neighbourhood::Moore, life);
+        assert_eq!(automaton.run(None).generations, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_order() -> color_eyre::Result<()> {
+        // A cell lights up once any neighbour is lit
+        let spread = |cell: &bool, neighbours: &[bool]| *cell || neighbours.iter().any(|n| *n);
+        let line = parse(&["#...."]);
+        let mut synchronous = Automaton::new(line.clone(), Neighbourhood::Orthogonal, spread);
+        assert_eq!(synchronous.step(), 1);
+        assert_eq!(synchronous.run(None).generations, 4);
+        let mut in_place =
+            Automaton::new(line, Neighbourhood::Orthogonal, spread).with_update(Update::InPlace);
+        assert_eq!(in_place.step(), 4);
+        assert_eq!(in_place.grid(), synchronous.grid());
+        assert!(in_place.run(None).fixed_point);
+        Ok(())
+    }
+}
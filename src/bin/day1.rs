@@ -1,7 +1,9 @@
+use advent_of_code2025::modular::{ModInt, multiples_crossed};
 use color_eyre::Result;
-use num::{Integer, abs};
 use std::time::Instant;
 
+type Dial = ModInt<100>;
+
 #[derive(Debug, Clone)]
 pub struct Input {
 distance: i32
@@ -36,7 +38,7 @@ mod parsing {
     use nom::{combinator::map, Finish, IResult};
 
     fn parse_line(input: &str) -> IResult<&str, Input> {
-        map(pair(take(1 as u32), parse_number::<i32>), |(direction, distance)| Input {distance: if direction == "L" {
+        map(pair(take(1u32), parse_number::<i32>), |(direction, distance)| Input {distance: if direction == "L" {
             -distance
         } else {
             distance
@@ -53,9 +55,9 @@ mod parsing {
 
 fn solve_part1(input: &[Input]) -> u32 {
     let mut result = 0;
-    input.iter().fold(50, |mut acc, i| {
-        acc = (acc + i.distance) % 100;
-        if acc == 0 {
+    input.iter().fold(Dial::new(50), |mut acc, i| {
+        acc += i.distance as i64;
+        if acc == Dial::new(0) {
             result += 1;
         }
         acc
@@ -65,28 +67,10 @@ fn solve_part1(input: &[Input]) -> u32 {
 
 fn solve_part2(input: &[Input]) -> u32 {
     let mut result: u32 = 0;
-    input.iter().fold(50, |mut acc: i32, i| {
-        for _ in 0..abs(i.distance) {
-            if i.distance < 0 {
-                acc = (acc - 1).rem_euclid(100)
-            } else {
-                acc = (acc + 1).rem_euclid(100)
-            }
-            if acc == 0 {
-                result += 1
-            }
-        }
-        acc
-        // let new_position = (acc + i.distance).rem_euclid(100);
-        // if (acc + i.distance) != new_position {
-        //     result += 1
-        // } else if new_position == 0 {
-        //     result += 1
-        // }
-        // if abs(i.distance) >= 100 {
-        //     result += (abs(i.distance).div_floor(&100) as u32) - 1;
-        // }
-        // new_position
+    input.iter().fold(Dial::new(50), |acc, i| {
+        // Count every click that lands on zero, rather than just where the turn ends
+        result += multiples_crossed(acc.value() as i64, i.distance as i64, 100) as u32;
+        acc + i.distance as i64
     });
     result
 }
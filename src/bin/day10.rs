@@ -1,16 +1,16 @@
+use advent_of_code2025::bitset::BitSet;
+use advent_of_code2025::gf2::{BitVector, Matrix};
+use advent_of_code2025::ilp::IntegerProgram;
 use color_eyre::Result;
-use itertools::{Itertools, repeat_n};
-use petgraph::{
-    algo::dijkstra,
-    graph::{DiGraph, NodeIndex},
-};
-use std::{collections::{HashMap, VecDeque}, time::Instant};
+use color_eyre::eyre::eyre;
+use itertools::Itertools;
+use std::time::Instant;
 
 #[derive(Debug, Clone)]
 pub struct Input {
-    // Use for bits
-    target_state: Vec<bool>,
-    buttons: Vec<Vec<bool>>,
+    target_state: BitSet,
+    lights: usize,
+    buttons: Vec<BitSet>,
     joltage: Vec<u32>,
 }
 
@@ -20,13 +20,13 @@ fn main() -> Result<()> {
     let time = Instant::now();
     println!(
         "Part 1: {} in {}ms",
-        solve_part1(&input),
+        solve_part1(&input)?,
         time.elapsed().as_millis()
     );
     let time = Instant::now();
     println!(
         "Part 2: {} in {}ms",
-        solve_part2(&input),
+        solve_part2(&input)?,
         time.elapsed().as_millis()
     );
     Ok(())
@@ -35,52 +35,29 @@ fn main() -> Result<()> {
 mod parsing {
 
     use super::Input;
-    use advent_of_code2025::parsing::parse_number;
+    use advent_of_code2025::bitset::{parse_indices, parse_pattern};
+    use advent_of_code2025::parsing::parse_bracketed_list;
     use color_eyre::Result;
-    use itertools::Itertools;
-    use nom::branch::alt;
     use nom::character::complete::space1;
     use nom::error::Error;
-    use nom::multi::{many1, separated_list1};
+    use nom::multi::separated_list1;
     use nom::sequence::{delimited, tuple};
     use nom::{Finish, IResult, bytes::complete::tag, combinator::map};
 
     fn parse_line(input: &str) -> IResult<&str, Input> {
         map(
             tuple((
-                delimited(
-                    tag("["),
-                    many1(map(alt((tag("."), tag("#"))), |c| match c {
-                        "." => false,
-                        "#" => true,
-                        _ => panic!("Nope"),
-                    })),
-                    tag("]"),
-                ),
+                delimited(tag("["), parse_pattern, tag("]")),
                 space1,
-                separated_list1(
-                    tag(" "),
-                    delimited(tag("("), separated_list1(tag(","), parse_number), tag(")")),
-                ),
+                separated_list1(tag(" "), parse_indices),
                 space1,
-                delimited(tag("{"), separated_list1(tag(","), parse_number), tag("}")),
+                parse_bracketed_list("{", ",", "}"),
             )),
-            |(target_state, _, buttons, _, joltage): (_, _, Vec<Vec<usize>>, _, _)| {
-                let state_size = target_state.len();
-                Input {
-                    target_state,
-                    buttons: buttons
-                        .iter()
-                        .map(|bs| {
-                            vec![false; state_size]
-                                .iter()
-                                .enumerate()
-                                .map(|(i, _)| bs.contains(&i))
-                                .collect_vec()
-                        })
-                        .collect_vec(),
-                    joltage,
-                }
+            |((target_state, lights), _, buttons, _, joltage)| Input {
+                target_state,
+                lights,
+                buttons,
+                joltage,
             },
         )(input)
     }
@@ -93,87 +70,45 @@ mod parsing {
     }
 }
 
-fn solve_part1(input: &[Input]) -> u32 {
+fn solve_part1(input: &[Input]) -> Result<u32> {
     let mut result = 0;
     for i in input {
-        let size = i.target_state.len();
-        let mut g = DiGraph::new();
-        let all_states: HashMap<Vec<bool>, NodeIndex> = repeat_n(vec![false, true], size)
-            .multi_cartesian_product()
-            .map(|s| {
-                let index = g.add_node(s.clone());
-                (s, index)
-            })
-            .collect();
-        // Instead of proper search, just traverse every possible state
-        // There's only like 1024, it's fine-ish even if some are impossible to get to
-        for (possible_state, state_index) in all_states.iter() {
-            for button in &i.buttons {
-                let new_state = possible_state
-                    .clone()
-                    .iter()
-                    .enumerate()
-                    .map(|(i, b)| b ^ button[i])
-                    .collect_vec();
-                g.add_edge(*state_index, *all_states.get(&new_state).unwrap(), 1);
-            }
-        }
-        // Now find the shortest path from start to end
-        let source_index = *all_states.get(&vec![false; size]).unwrap();
-        let target_index = *all_states.get(&i.target_state).unwrap();
-        let costs = dijkstra(&g, source_index, Some(target_index), |_| 1);
-        result += costs.get(&target_index).unwrap();
+        // Each button toggles a column of lights, so this is Ax = target over GF(2)
+        let buttons = i
+            .buttons
+            .iter()
+            .map(|b| BitVector::from_bitset(*b, i.lights))
+            .collect_vec();
+        let lights = Matrix::from_columns(&buttons, i.lights);
+        let target = BitVector::from_bitset(i.target_state, i.lights);
+        let presses = lights
+            .min_weight_solution(&target)?
+            .ok_or_else(|| eyre!("no presses reach lights {:?}", i.target_state))?;
+        result += presses.count_ones() as u32;
     }
-    result
+    Ok(result)
 }
 
-fn solve_part2(input: &[Input]) -> u32 {
+fn solve_part2(input: &[Input]) -> Result<u32> {
     let mut result = 0;
-    for (row, i) in input.iter().enumerate() {
-        println!("{}", row);
-        let size = i.joltage.len();
-        let mut g = DiGraph::new();
-        let mut all_states: HashMap<Vec<u32>, NodeIndex> = HashMap::new();
-        // We have to do proper search now :(
-        let mut to_visit = VecDeque::new();
-        let initial = vec![0; size];
-        let initial_index = g.add_node(initial.clone());
-        to_visit.push_back((initial, initial_index));
-        // TODO: it would probably be faster to do DFS, trying the button presses with the most digits first
-        // since if we need to have N total changes, doing more at once is better
-        // and again we could stop once we reach it, since the first search reaching there should be optimal
-        while let Some((state, state_index)) = to_visit.pop_front() {
-            // since we're doing BFS, as soon as we reach the target state we can stop since it must be the shortest path
-            // We could skip the graph search by storing our depth
-            if state == i.joltage {
-                break;
-            }
-            for button in &i.buttons {
-                let new_state = state.clone().iter().enumerate().map(|(i, b)| {
-                    if button[i] {
-                        b + 1
-                    } else {
-                        *b
-                    }
-                }).collect_vec();
-                if new_state.iter().enumerate().any(|(index, value)| *value > i.joltage[index]) {
-                    // If we've gone over the target, give up
-                    continue;
-                }
-                let target_index = all_states.entry(new_state.clone()).or_insert_with(|| {
-                    let i = g.add_node(new_state.clone());
-                    to_visit.push_back((new_state.clone(), i));
-                    i
-                });
-                g.add_edge(state_index, *target_index, 1);
-            }
-        }
-        // Now find the shortest path from start to end
-        let target_index = *all_states.get(&i.joltage).unwrap();
-        let costs = dijkstra(&g, initial_index, Some(target_index), |_| 1);
-        result += costs.get(&target_index).unwrap();
+    for i in input {
+        // Each counter is the sum of the presses of the buttons that touch it
+        let constraints = (0..i.joltage.len())
+            .map(|counter| {
+                i.buttons
+                    .iter()
+                    .map(|b| b.contains(counter) as i64)
+                    .collect_vec()
+            })
+            .collect_vec();
+        let targets = i.joltage.iter().map(|j| *j as i64).collect_vec();
+        let program = IntegerProgram::new(constraints, targets, vec![1; i.buttons.len()]);
+        let presses = program
+            .minimise()?
+            .ok_or_else(|| eyre!("no presses reach joltages {:?}", i.joltage))?;
+        result += presses.objective as u32;
     }
-    result
+    Ok(result)
 }
 
 #[cfg(test)]
@@ -183,7 +118,7 @@ mod tests {
     #[test]
     fn test_part1() -> color_eyre::Result<()> {
         let input = parsing::parse_input(include_str!("../../input/day10.test.txt"))?;
-        let result = solve_part1(&input);
+        let result = solve_part1(&input)?;
         assert_eq!(result, 7);
         Ok(())
     }
@@ -191,7 +126,7 @@ mod tests {
     #[test]
     fn test_part2() -> color_eyre::Result<()> {
         let input = parsing::parse_input(include_str!("../../input/day10.test.txt"))?;
-        let result = solve_part2(&input);
+        let result = solve_part2(&input)?;
         assert_eq!(result, 33);
         Ok(())
     }
@@ -1,6 +1,7 @@
+use advent_of_code2025::digits::halves;
+use advent_of_code2025::number_theory::{divisors, mobius};
 use advent_of_code2025::range::Range;
 use color_eyre::Result;
-use itertools::Itertools;
 use std::time::Instant;
 
 #[derive(Debug, Clone)]
@@ -46,41 +47,50 @@ mod parsing {
     }
 }
 
-fn solve_part1(input: &Vec<Range<u64>>) -> u64 {
-    input.iter().fold(0, |acc, r| {
-        acc + (r.lower..=r.upper).into_iter().fold(0, |inner, x| {
-            let mut text = x.to_string();
-            if text.len() % 2 == 0 {
-                let second_half = text.split_off(text.len() / 2);
-                if second_half == text {
-                    return inner + x;
-                }
-            }
-            inner
-        })
-    })
+fn solve_part1(input: &[Range<u64>]) -> u64 {
+    input
+        .iter()
+        .flat_map(|r| r.lower..=r.upper)
+        .filter(|x| halves(*x).is_some_and(|(high, low)| high == low))
+        .sum()
+}
+
+fn solve_part2(input: &[Range<u64>]) -> u64 {
+    input
+        .iter()
+        .map(|r| repeated_block_sum(r.lower, r.upper))
+        .sum()
 }
 
-fn solve_part2(input: &Vec<Range<u64>>) -> u64 {
-    input.iter().fold(0, |acc, r| {
-        acc + (r.lower..=r.upper).into_iter().fold(0, |inner, x| {
-            let text = x.to_string();
-            for chunk_size in 1..=(text.len() / 2) {
-                if text.len() % chunk_size == 0 {
-                    if text
-                        .chars()
-                        .chunks(chunk_size)
-                        .into_iter()
-                        .map(|chars| chars.collect_vec())
-                        .all_equal()
-                    {
-                        return inner + x;
-                    }
-                }
+// The total of the numbers in lower..=upper made of a block repeated at least twice.
+//
+// A d-digit number made of k-digit blocks is the block times 1 0..0 1 0..0 1, so those
+// in a range are an arithmetic series. Every repeated number repeats a block of length
+// d / p for some prime p dividing d, and the overlaps are again repeated blocks, so
+// inclusion-exclusion over square-free divisors counts each exactly once.
+fn repeated_block_sum(lower: u64, upper: u64) -> u64 {
+    let mut total: i128 = 0;
+    for digits in 1..=20u32 {
+        let smallest = 10u128.pow(digits - 1);
+        let (lower, upper) = (
+            (lower as u128).max(smallest),
+            (upper as u128).min(smallest * 10 - 1),
+        );
+        if lower > upper {
+            continue;
+        }
+        for repeats in divisors(digits as u64).into_iter().skip(1) {
+            let block_digits = digits / repeats as u32;
+            let multiplier = (10u128.pow(digits) - 1) / (10u128.pow(block_digits) - 1);
+            let first = lower.div_ceil(multiplier);
+            let last = upper / multiplier;
+            if first <= last {
+                let sum = multiplier * (first + last) * (last - first + 1) / 2;
+                total -= mobius(repeats) as i128 * sum as i128;
             }
-            inner
-        })
-    })
+        }
+    }
+    total as u64
 }
 
 #[cfg(test)]
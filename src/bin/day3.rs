@@ -25,15 +25,16 @@ fn main() -> Result<()> {
 
 mod parsing {
 
+    use advent_of_code2025::parsing::parse_digits;
     use color_eyre::Result;
-    use nom::bytes::complete::take;
-    use nom::combinator::map_res;
+    use nom::combinator::map;
     use nom::error::Error;
-    use nom::multi::many1;
     use nom::{Finish, IResult};
 
     fn parse_line(input: &str) -> IResult<&str, Vec<u128>> {
-        many1(map_res(take(1u8), |c| u128::from_str_radix(c, 10)))(input)
+        map(parse_digits, |digits| {
+            digits.into_iter().map(u128::from).collect()
+        })(input)
     }
 
     pub fn parse_input(input: &str) -> Result<Vec<Vec<u128>>, Error<&str>> {
@@ -44,7 +45,7 @@ mod parsing {
     }
 }
 
-fn solve_part1(input: &Vec<Vec<u128>>) -> u128 {
+fn solve_part1(input: &[Vec<u128>]) -> u128 {
     // find largest two digit number
     input
         .iter()
@@ -76,7 +77,7 @@ fn solve_part1(input: &Vec<Vec<u128>>) -> u128 {
         .sum()
 }
 
-fn solve_part2(input: &Vec<Vec<u128>>) -> u128 {
+fn solve_part2(input: &[Vec<u128>]) -> u128 {
     // find largest twelve digit number
     input
         .iter()
@@ -1,4 +1,6 @@
+use advent_of_code2025::automaton::{Automaton, Neighbourhood};
 use advent_of_code2025::coordinate::Coordinate;
+use advent_of_code2025::prefix_sum::SummedAreaTable;
 use color_eyre::Result;
 use std::time::Instant;
 
@@ -52,22 +54,14 @@ mod parsing {
 }
 
 fn solve_part1(input: &[Vec<Item>]) -> u32 {
-    let max_row = input.len();
-    let max_col = input[0].len();
+    let papers = SummedAreaTable::new(input, |item| (*item == Item::PAPER) as u32);
     let mut result = 0;
-    for row in 0..max_row {
-        for col in 0..max_col {
+    for row in 0..input.len() {
+        for col in 0..input[0].len() {
             let coord = Coordinate::new(row, col);
-            if coord.get(input) == Item::PAPER {
-                let mut count = 0;
-                for adj in coord.get_adjacent_points_diagonal(max_row, max_col) {
-                    if adj.get(input) == Item::PAPER {
-                        count += 1;
-                    }
-                }
-                if count < 4 {
-                    result += 1;
-                }
+            // The 3x3 block around a roll includes the roll itself
+            if coord.get(input) == Item::PAPER && papers.sum_around(coord, 1) - 1 < 4 {
+                result += 1;
             }
         }
     }
@@ -75,35 +69,19 @@ fn solve_part1(input: &[Vec<Item>]) -> u32 {
 }
 
 fn solve_part2(input: &[Vec<Item>]) -> u32 {
-    let max_row = input.len();
-    let max_col = input[0].len();
-    let mut result = 0;
-    let mut prev_result;
-    let mut current_grid: Vec<Vec<Item>> = input.to_vec();
-    loop {
-        prev_result = result;
-        for row in 0..max_row {
-            for col in 0..max_col {
-                let coord = Coordinate::new(row, col);
-                if coord.get(&current_grid) == Item::PAPER {
-                    let mut count = 0;
-                    for adj in coord.get_adjacent_points_diagonal(max_row, max_col) {
-                        if adj.get(&current_grid) == Item::PAPER {
-                            count += 1;
-                        }
-                    }
-                    if count < 4 {
-                        result += 1;
-                        current_grid[row][col] = Item::EMPTY;
-                    }
-                }
-            }
-        }
-        if result == prev_result {
-            break;
+    let count = |grid: &[Vec<Item>]| grid.iter().flatten().filter(|i| **i == Item::PAPER).count();
+    // A roll with fewer than four neighbouring rolls can be taken away, which may free up
+    // its neighbours in turn
+    let mut removal = Automaton::new(input.to_vec(), Neighbourhood::Moore, |item, neighbours| {
+        let rolls = neighbours.iter().filter(|n| **n == Item::PAPER).count();
+        if *item == Item::PAPER && rolls < 4 {
+            Item::EMPTY
+        } else {
+            *item
         }
-    }
-    result
+    });
+    removal.run(None);
+    (count(input) - count(removal.grid())) as u32
 }
 
 #[cfg(test)]
@@ -1,11 +1,9 @@
-use advent_of_code2025::range::Range;
+use advent_of_code2025::compression::CompressedShape;
+use advent_of_code2025::coordinate::Coordinate;
+use advent_of_code2025::geometry::{Polygon, largest_rectangle};
 use color_eyre::Result;
 use itertools::Itertools;
-use num::abs;
-use std::{
-    cmp::{max, min},
-    time::Instant,
-};
+use std::time::Instant;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Input {
@@ -13,6 +11,12 @@ pub struct Input {
     y: i64,
 }
 
+impl Input {
+    fn point(&self) -> Coordinate<i64> {
+        Coordinate::new(self.y, self.x)
+    }
+}
+
 #[allow(dead_code)]
 fn main() -> Result<()> {
     let input = parsing::parse_input(include_str!("../../input/day9.txt"))?;
@@ -34,17 +38,13 @@ fn main() -> Result<()> {
 mod parsing {
 
     use super::Input;
-    use advent_of_code2025::parsing::parse_number;
+    use advent_of_code2025::parsing::parse_tuple;
     use color_eyre::Result;
     use nom::error::Error;
-    use nom::sequence::separated_pair;
-    use nom::{Finish, IResult, bytes::complete::tag, combinator::map};
+    use nom::{Finish, IResult, combinator::map};
 
     fn parse_line(input: &str) -> IResult<&str, Input> {
-        map(
-            separated_pair(parse_number, tag(","), parse_number),
-            |(x, y)| Input { x, y },
-        )(input)
+        map(parse_tuple(","), |[x, y]| Input { x, y })(input)
     }
 
     pub fn parse_input(input: &str) -> Result<Vec<Input>, Error<&str>> {
@@ -56,107 +56,17 @@ mod parsing {
 }
 
 fn solve_part1(input: &[Input]) -> i64 {
-    input
-        .iter()
-        .cartesian_product(input.iter())
-        .map(|(a, b)| (abs(b.x - a.x) + 1) * (abs(b.y - a.y) + 1))
-        .sorted()
-        .rev()
-        .max()
-        .unwrap()
+    let corners = input.iter().map(Input::point).collect_vec();
+    largest_rectangle(&corners, |_| true).unwrap().tile_count()
 }
 
 fn solve_part2(input: &[Input]) -> i64 {
-    let horizontal_boundaries = input
-        .iter()
-        .chain(vec![input[0]].iter())
-        .tuple_windows()
-        .filter_map(|(a, b)| {
-            if a.x == b.x {
-                None
-            } else {
-                Some((
-                    a.y,
-                    Range {
-                        lower: min(a.x, b.x),
-                        upper: max(a.x, b.x),
-                    },
-                ))
-            }
-        })
-        .collect_vec();
-    let vertical_boundaries = input
-        .iter()
-        .chain(vec![input[0]].iter())
-        .tuple_windows()
-        .filter_map(|(a, b)| {
-            if a.y == b.y {
-                None
-            } else {
-                Some((
-                    a.x,
-                    Range {
-                        lower: min(a.y, b.y),
-                        upper: max(a.y, b.y),
-                    },
-                ))
-            }
-        })
-        .collect_vec();
-
-    let (corner_a, corner_b, size) = input
-        .iter()
-        .cartesian_product(input.iter())
-        .map(|(a, b)| (a, b, (abs(b.x - a.x) + 1) * (abs(b.y - a.y) + 1)))
-        .sorted_by_key(|x| x.2)
-        .rev()
-        .find(|(a, b, _)| {
-            // println!("{:?}, {:?}", a, b);
-            // Filter for a valid rectangle, starting from the largest
-            // Walk the edges, checking to see if we cross a boundary
-            validate_boundary_crossing(&horizontal_boundaries, a, &Input { x: a.x, y: b.y })
-                && validate_boundary_crossing(&horizontal_boundaries, b, &Input { x: b.x, y: a.y })
-                && validate_boundary_crossing(&vertical_boundaries, a, &Input { x: b.x, y: a.y })
-                && validate_boundary_crossing(&vertical_boundaries, &b, &Input { x: a.x, y: b.y })
-        })
-        .unwrap();
-    println!("{:?}, {:?}, {}", corner_a, corner_b, size);
-    size
-}
-
-fn validate_boundary_crossing(
-    boundaries: &Vec<(i64, Range<i64>)>,
-    starting_point: &Input,
-    end_point: &Input,
-) -> bool {
-    let stable;
-    let dynamic_start;
-    let dynamic_end;
-    if starting_point.x == end_point.x {
-        stable = starting_point.x;
-        dynamic_start = starting_point.y;
-        dynamic_end = end_point.y;
-    } else {
-        stable = starting_point.y;
-        dynamic_start = starting_point.x;
-        dynamic_end = end_point.x;
-    };
-
-    for dynamic in min(dynamic_start, dynamic_end)..=max(dynamic_start, dynamic_end) {
-        // Don't need to check our starting point, since it's guaranteed good
-        if dynamic == dynamic_start || dynamic == dynamic_end {
-            continue;
-        }
-        if boundaries
-            .iter()
-            .find(|(boundary_dynamic, r)| *boundary_dynamic == dynamic && r.contains(stable))
-            .is_some()
-        {
-            // println!("Found boundary at {},{}", stable, dynamic);
-            return false;
-        }
-    }
-    true
+    // The red tiles are the corners of a loop, and the tiles inside it are green
+    let corners = input.iter().map(Input::point).collect_vec();
+    let shape = CompressedShape::from_polygon(&Polygon::new(corners.clone()));
+    largest_rectangle(&corners, |r| shape.contains_rectangle(r))
+        .unwrap()
+        .tile_count()
 }
 
 #[cfg(test)]
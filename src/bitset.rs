@@ -0,0 +1,222 @@
+use std::fmt::{self, Debug};
+use std::ops::{BitAnd, BitOr, BitXor, BitXorAssign};
+
+use nom::IResult;
+use nom::bytes::complete::take_while1;
+use nom::combinator::{map, verify};
+
+use crate::parsing::parse_bracketed_list;
+
+/// A set of small indices packed into a `u128`. It's `Copy`, hashes and compares as a
+/// single integer, so it makes a cheap search state for up to 128 on/off values.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BitSet {
+    bits: u128,
+}
+
+impl BitSet {
+    /// The largest index plus one
+    pub const CAPACITY: usize = 128;
+
+    pub fn new() -> BitSet {
+        BitSet { bits: 0 }
+    }
+
+    pub fn from_bits(bits: u128) -> BitSet {
+        BitSet { bits }
+    }
+
+    pub fn bits(&self) -> u128 {
+        self.bits
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        i < BitSet::CAPACITY && self.bits & (1 << i) != 0
+    }
+
+    pub fn insert(&mut self, i: usize) {
+        assert!(i < BitSet::CAPACITY, "index {} out of range", i);
+        self.bits |= 1 << i;
+    }
+
+    pub fn remove(&mut self, i: usize) {
+        assert!(i < BitSet::CAPACITY, "index {} out of range", i);
+        self.bits &= !(1 << i);
+    }
+
+    pub fn toggle(&mut self, i: usize) {
+        assert!(i < BitSet::CAPACITY, "index {} out of range", i);
+        self.bits ^= 1 << i;
+    }
+
+    /// The number of indices in the set, i.e. the popcount
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    pub fn is_subset(&self, other: &BitSet) -> bool {
+        self.bits & !other.bits == 0
+    }
+
+    /// The indices in the set, smallest first
+    pub fn iter(&self) -> Iter {
+        Iter { bits: self.bits }
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = BitSet::new();
+        for i in iter {
+            set.insert(i);
+        }
+        set
+    }
+}
+
+impl IntoIterator for BitSet {
+    type Item = usize;
+    type IntoIter = Iter;
+
+    fn into_iter(self) -> Iter {
+        self.iter()
+    }
+}
+
+/// Iterator over the indices in a `BitSet`, clearing the lowest bit each step
+#[derive(Clone, Debug)]
+pub struct Iter {
+    bits: u128,
+}
+
+impl Iterator for Iter {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.bits == 0 {
+            return None;
+        }
+        let i = self.bits.trailing_zeros() as usize;
+        self.bits &= self.bits - 1;
+        Some(i)
+    }
+}
+
+impl Debug for BitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl BitXor for BitSet {
+    type Output = BitSet;
+
+    fn bitxor(self, rhs: BitSet) -> BitSet {
+        BitSet::from_bits(self.bits ^ rhs.bits)
+    }
+}
+
+impl BitXorAssign for BitSet {
+    fn bitxor_assign(&mut self, rhs: BitSet) {
+        self.bits ^= rhs.bits;
+    }
+}
+
+impl BitOr for BitSet {
+    type Output = BitSet;
+
+    fn bitor(self, rhs: BitSet) -> BitSet {
+        BitSet::from_bits(self.bits | rhs.bits)
+    }
+}
+
+impl BitAnd for BitSet {
+    type Output = BitSet;
+
+    fn bitand(self, rhs: BitSet) -> BitSet {
+        BitSet::from_bits(self.bits & rhs.bits)
+    }
+}
+
+/// A pattern like `.##.`, where `#` at position `i` means `i` is in the set.
+/// Also returns the length of the pattern, which the set itself doesn't keep.
+pub fn parse_pattern(input: &str) -> IResult<&str, (BitSet, usize)> {
+    map(
+        verify(take_while1(|c| c == '.' || c == '#'), |s: &str| {
+            s.len() <= BitSet::CAPACITY
+        }),
+        |s: &str| {
+            let set = s
+                .char_indices()
+                .filter(|(_, c)| *c == '#')
+                .map(|(i, _)| i)
+                .collect();
+            (set, s.len())
+        },
+    )(input)
+}
+
+/// A list of indices like `(0,3,4)`
+pub fn parse_indices(input: &str) -> IResult<&str, BitSet> {
+    map(
+        verify(
+            parse_bracketed_list("(", ",", ")"),
+            |indices: &Vec<usize>| indices.iter().all(|i| *i < BitSet::CAPACITY),
+        ),
+        |indices| indices.into_iter().collect(),
+    )(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::bfs;
+
+    #[test]
+    fn test_bit_set() -> color_eyre::Result<()> {
+        let mut a = [0, 64, 127].into_iter().collect::<BitSet>();
+        let b = BitSet::from_bits(0b1001);
+        assert_eq!(a.len(), 3);
+        assert_eq!((a ^ b).iter().collect::<Vec<_>>(), vec![3, 64, 127]);
+        assert_eq!(a & b, BitSet::from_bits(1));
+        assert!(BitSet::from_bits(1).is_subset(&b));
+        a.toggle(64);
+        a.remove(0);
+        assert_eq!(format!("{:?}", a), "{127}");
+        assert!(!a.contains(200));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse() -> color_eyre::Result<()> {
+        assert_eq!(
+            parse_pattern(".##.] rest")?,
+            ("] rest", (BitSet::from_bits(0b0110), 4))
+        );
+        assert_eq!(parse_indices("(0,3,4)")?, ("", BitSet::from_bits(0b11001)));
+        assert!(parse_indices("(0,128)").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_key() -> color_eyre::Result<()> {
+        // Toggling {0, 100}, {100, 127} and {0} once each is the only way to {127}, so
+        // states above the low 64 bits must hash and compare correctly
+        let toggles = ["(0,100)", "(100,127)", "(0)"]
+            .iter()
+            .map(|t| parse_indices(t).map(|(_, set)| set))
+            .collect::<Result<Vec<_>, _>>()?;
+        let target = BitSet::from_bits(1 << 127);
+        let found = bfs(
+            BitSet::new(),
+            |state| toggles.iter().map(|t| *state ^ *t).collect::<Vec<_>>(),
+            |state| *state == target,
+        );
+        assert_eq!(found.map(|f| f.cost), Some(3));
+        Ok(())
+    }
+}
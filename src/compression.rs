@@ -0,0 +1,268 @@
+use itertools::Itertools;
+
+use crate::coordinate::{Coordinate, IndexingCoordinate};
+use crate::geometry::{Polygon, Rectangle};
+use crate::prefix_sum::SummedAreaTable;
+
+/// Coordinate compression along one axis: each distinct value gets its own cell, and
+/// optionally each run of values between them is collapsed into a single gap cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Axis {
+    // The inclusive range of original values each cell stands for, in order
+    cells: Vec<(i64, i64)>,
+}
+
+/// Compression of both axes of the plane
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Compression {
+    pub rows: Axis,
+    pub cols: Axis,
+}
+
+/// A polygon rasterised onto a compressed grid, which can answer whether a rectangle is
+/// inside it in O(1) however large the original coordinates are
+#[derive(Clone, Debug)]
+pub struct CompressedShape {
+    compression: Compression,
+    inside: Vec<Vec<bool>>,
+    outside_counts: SummedAreaTable<usize>,
+}
+
+impl Axis {
+    /// Without gaps only the given values can be compressed. With them, every value
+    /// between the smallest and largest has a cell.
+    pub fn new(values: impl IntoIterator<Item = i64>, gaps: bool) -> Axis {
+        let values = values.into_iter().sorted().dedup().collect_vec();
+        let mut cells = vec![];
+        for (i, value) in values.iter().enumerate() {
+            if gaps && i > 0 && values[i - 1] + 1 < *value {
+                cells.push((values[i - 1] + 1, value - 1));
+            }
+            cells.push((*value, *value));
+        }
+        Axis { cells }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// The cell containing `value`, if there is one
+    pub fn compress(&self, value: i64) -> Option<usize> {
+        let index = self.cells.partition_point(|(_, upper)| *upper < value);
+        self.cells
+            .get(index)
+            .filter(|(lower, _)| *lower <= value)
+            .map(|_| index)
+    }
+
+    /// The inclusive range of original values in cell `index`
+    pub fn expand(&self, index: usize) -> (i64, i64) {
+        self.cells[index]
+    }
+}
+
+impl Compression {
+    pub fn new(points: &[Coordinate<i64>], gaps: bool) -> Compression {
+        Compression {
+            rows: Axis::new(points.iter().map(|p| p.row), gaps),
+            cols: Axis::new(points.iter().map(|p| p.col), gaps),
+        }
+    }
+
+    pub fn compress_point(&self, point: &Coordinate<i64>) -> Option<Coordinate<i64>> {
+        Some(Coordinate::new(
+            self.rows.compress(point.row)? as i64,
+            self.cols.compress(point.col)? as i64,
+        ))
+    }
+
+    /// The original points a compressed cell stands for
+    pub fn expand_point(&self, cell: &Coordinate<i64>) -> Rectangle {
+        let (min_row, max_row) = self.rows.expand(cell.row as usize);
+        let (min_col, max_col) = self.cols.expand(cell.col as usize);
+        Rectangle::from_corners(
+            Coordinate::new(min_row, min_col),
+            Coordinate::new(max_row, max_col),
+        )
+    }
+
+    /// The cells covering a rectangle
+    pub fn compress_rectangle(&self, rectangle: &Rectangle) -> Option<Rectangle> {
+        Some(Rectangle::from_corners(
+            self.compress_point(&rectangle.min)?,
+            self.compress_point(&rectangle.max)?,
+        ))
+    }
+
+    /// The original points covered by a rectangle of cells
+    pub fn expand_rectangle(&self, rectangle: &Rectangle) -> Rectangle {
+        Rectangle::from_corners(
+            self.expand_point(&rectangle.min).min,
+            self.expand_point(&rectangle.max).max,
+        )
+    }
+
+    /// The same polygon in compressed space. It keeps the same shape as long as every
+    /// vertex has its own cell, which is the case if it was compressed from them.
+    pub fn compress_polygon(&self, polygon: &Polygon) -> Option<Polygon> {
+        let vertices = polygon
+            .vertices()
+            .iter()
+            .map(|v| self.compress_point(v))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Polygon::new(vertices))
+    }
+}
+
+impl CompressedShape {
+    /// Compress the polygon's vertices with gaps, padded by a ring of cells outside it,
+    /// and fill in the cells inside
+    pub fn from_polygon(polygon: &Polygon) -> CompressedShape {
+        let vertices = polygon.vertices();
+        let min_row = vertices.iter().map(|v| v.row).min().unwrap() - 1;
+        let max_row = vertices.iter().map(|v| v.row).max().unwrap() + 1;
+        let min_col = vertices.iter().map(|v| v.col).min().unwrap() - 1;
+        let max_col = vertices.iter().map(|v| v.col).max().unwrap() + 1;
+        let corners = [
+            Coordinate::new(min_row, min_col),
+            Coordinate::new(max_row, max_col),
+        ];
+        let compression = Compression::new(
+            &vertices.iter().chain(&corners).copied().collect_vec(),
+            true,
+        );
+        let compressed = compression.compress_polygon(polygon).unwrap();
+        let inside = fill(&compressed, compression.rows.len(), compression.cols.len());
+        let outside_counts = SummedAreaTable::new(&inside, |cell| !cell as usize);
+        CompressedShape {
+            compression,
+            inside,
+            outside_counts,
+        }
+    }
+
+    pub fn compression(&self) -> &Compression {
+        &self.compression
+    }
+
+    /// Whether a compressed cell is inside the polygon, including its boundary
+    pub fn is_inside(&self, cell: &Coordinate<i64>) -> bool {
+        self.inside[cell.row as usize][cell.col as usize]
+    }
+
+    /// Whether every point of the rectangle, in original coordinates, is inside the
+    /// polygon or on its boundary
+    pub fn contains_rectangle(&self, rectangle: &Rectangle) -> bool {
+        let Some(cells) = self.compression.compress_rectangle(rectangle) else {
+            return false;
+        };
+        let top_left = IndexingCoordinate::new(cells.min.row as usize, cells.min.col as usize);
+        let bottom_right = IndexingCoordinate::new(cells.max.row as usize, cells.max.col as usize);
+        self.outside_counts.sum(top_left, bottom_right) == 0
+    }
+}
+
+// Mark the boundary cells, then flood fill from the corner, which the padding guarantees
+// is outside. Whatever the flood doesn't reach is inside.
+fn fill(polygon: &Polygon, rows: usize, cols: usize) -> Vec<Vec<bool>> {
+    let mut boundary = vec![vec![false; cols]; rows];
+    for edge in polygon.edges() {
+        let bounds = edge.bounds();
+        for row in bounds.min.row..=bounds.max.row {
+            for col in bounds.min.col..=bounds.max.col {
+                boundary[row as usize][col as usize] = true;
+            }
+        }
+    }
+    let mut outside = vec![vec![false; cols]; rows];
+    outside[0][0] = true;
+    let mut stack = vec![Coordinate::new(0, 0)];
+    while let Some(cell) = stack.pop() {
+        for next in cell.get_adjacent_points(rows, cols) {
+            if !boundary[next.row][next.col] && !outside[next.row][next.col] {
+                outside[next.row][next.col] = true;
+                stack.push(next);
+            }
+        }
+    }
+    outside
+        .into_iter()
+        .map(|row| row.into_iter().map(|o| !o).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_axis() -> color_eyre::Result<()> {
+        let axis = Axis::new([100, 5, 6, 100, 50], true);
+        assert_eq!(axis.len(), 6);
+        assert_eq!(axis.compress(5), Some(0));
+        assert_eq!(axis.compress(6), Some(1));
+        assert_eq!(axis.compress(30), Some(2));
+        assert_eq!(axis.expand(2), (7, 49));
+        assert_eq!(axis.compress(100), Some(5));
+        assert_eq!(axis.compress(101), None);
+        let axis = Axis::new([100, 5, 6, 100, 50], false);
+        assert_eq!(axis.len(), 4);
+        assert_eq!(axis.compress(30), None);
+        assert_eq!(axis.compress(50), Some(2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_contains_rectangle() -> color_eyre::Result<()> {
+        // A U shape with large coordinates: the gap between the arms is outside
+        let shape = Polygon::new(
+            [
+                (0, 0),
+                (0, 200),
+                (4000, 200),
+                (4000, 600),
+                (0, 600),
+                (0, 800),
+                (6000, 800),
+                (6000, 0),
+            ]
+            .into_iter()
+            .map(Coordinate::from)
+            .collect(),
+        );
+        let compressed = CompressedShape::from_polygon(&shape);
+        let rectangle = |a: (i64, i64), b: (i64, i64)| Rectangle::from_corners(a.into(), b.into());
+        for r in [
+            rectangle((0, 0), (6000, 200)),
+            rectangle((4000, 0), (6000, 800)),
+            rectangle((0, 0), (6000, 800)),
+            rectangle((1000, 300), (3000, 500)),
+            rectangle((4000, 200), (4000, 600)),
+            rectangle((3999, 300), (4000, 300)),
+            rectangle((100, 100), (5000, 150)),
+            rectangle((-5, 0), (0, 0)),
+        ] {
+            assert_eq!(
+                compressed.contains_rectangle(&r),
+                shape.contains_rectangle(&r),
+                "{:?}",
+                r
+            );
+        }
+        let compression = compressed.compression();
+        let cells = compression
+            .compress_rectangle(&rectangle((1, 1), (199, 199)))
+            .unwrap();
+        // Both rows fall in the gap between 0 and 4000, so expanding covers all of it
+        assert_eq!(
+            compression.expand_rectangle(&cells),
+            rectangle((1, 1), (3999, 199))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+//! Cycle detection for puzzles that ask for the state after a huge number of steps.
+//! Nothing uses it yet: day 1's dial is worked out in closed form, and day 4's removals
+//! settle into a fixed point that the automaton already stops at.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Where repeatedly stepping a state machine starts going round in circles: the state
+/// after `start` steps comes back again every `length` steps
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+}
+
+impl Cycle {
+    /// The earliest step with the same state as step `n`
+    pub fn equivalent_step(&self, n: usize) -> usize {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.length
+        }
+    }
+}
+
+/// Brent's algorithm, which uses O(1) memory and usually fewer steps than Floyd's.
+///
+/// Like Floyd's it only finishes if the sequence does cycle, which it must if there
+/// are finitely many states.
+pub fn brent<S: Clone + PartialEq>(initial: S, mut step: impl FnMut(&S) -> S) -> Cycle {
+    // Find the length by moving the hare ahead in doubling stretches, bringing the
+    // tortoise up to it at the start of each stretch
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial);
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = step(&hare);
+        length += 1;
+    }
+    // With the hare `length` ahead, they first meet at the start of the cycle
+    let mut tortoise = initial.clone();
+    let mut hare = initial;
+    for _ in 0..length {
+        hare = step(&hare);
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+    Cycle { start, length }
+}
+
+/// Floyd's tortoise and hare, which uses O(1) memory
+pub fn floyd<S: Clone + PartialEq>(initial: S, mut step: impl FnMut(&S) -> S) -> Cycle {
+    // The hare moves twice as fast, so they meet somewhere in the cycle
+    let mut tortoise = step(&initial);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        hare = step(&hare);
+    }
+    // The meeting point is as far from the start of the cycle as the initial state is
+    let mut start = 0;
+    tortoise = initial;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+    let mut length = 1;
+    hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        length += 1;
+    }
+    Cycle { start, length }
+}
+
+/// Remember every state until one repeats. This takes memory for every state before the
+/// repeat, but steps each state only once, so it suits expensive steps.
+pub fn find_cycle<S: Clone + Eq + Hash>(initial: S, step: impl FnMut(&S) -> S) -> Cycle {
+    match run(initial, step, None) {
+        Run::Cycled(cycle, _) => cycle,
+        Run::Reached(_) => unreachable!("run without a target only stops at a cycle"),
+    }
+}
+
+/// The state after `n` steps, skipping whole laps of the cycle once one is found
+pub fn state_at<S: Clone + Eq + Hash>(initial: S, step: impl FnMut(&S) -> S, n: usize) -> S {
+    match run(initial, step, Some(n)) {
+        Run::Cycled(cycle, mut history) => history.swap_remove(cycle.equivalent_step(n)),
+        Run::Reached(state) => state,
+    }
+}
+
+enum Run<S> {
+    // The cycle, and every state from the initial one up to just before the repeat
+    Cycled(Cycle, Vec<S>),
+    Reached(S),
+}
+
+fn run<S: Clone + Eq + Hash>(
+    initial: S,
+    mut step: impl FnMut(&S) -> S,
+    target: Option<usize>,
+) -> Run<S> {
+    let mut seen = HashMap::new();
+    let mut history = vec![];
+    let mut state = initial;
+    loop {
+        if target == Some(history.len()) {
+            return Run::Reached(state);
+        }
+        if let Some(start) = seen.get(&state) {
+            let cycle = Cycle {
+                start: *start,
+                length: history.len() - start,
+            };
+            return Run::Cycled(cycle, history);
+        }
+        seen.insert(state.clone(), history.len());
+        let next = step(&state);
+        history.push(state);
+        state = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0, 1, 2, 3, 4, 5, 2, 3, ...
+    fn rho(x: &u64) -> u64 {
+        if *x < 5 { x + 1 } else { 2 }
+    }
+
+    #[test]
+    fn test_cycle_detection() -> color_eyre::Result<()> {
+        let expected = Cycle {
+            start: 2,
+            length: 4,
+        };
+        assert_eq!(brent(0, rho), expected);
+        assert_eq!(floyd(0, rho), expected);
+        assert_eq!(find_cycle(0, rho), expected);
+        // A pseudo-random walk, where all three have to agree
+        let walk = |x: &u64| (x * x + 1) % 1_000_003;
+        let cycle = find_cycle(7, walk);
+        assert_eq!(brent(7, walk), cycle);
+        assert_eq!(floyd(7, walk), cycle);
+        // A fixed point is a cycle of length one
+        assert_eq!(
+            brent(3, |x| *x),
+            Cycle {
+                start: 0,
+                length: 1
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_state_at() -> color_eyre::Result<()> {
+        assert_eq!(state_at(0, rho, 3), 3);
+        assert_eq!(state_at(0, rho, 6), 2);
+        assert_eq!(state_at(0, rho, 1_000_000_000_000), 4);
+        // Stepping a long way round a big cycle gives the same as stepping directly
+        let walk = |x: &u64| (x * x + 1) % 1_000_003;
+        let mut direct = 7;
+        for _ in 0..100_000 {
+            direct = walk(&direct);
+        }
+        assert_eq!(state_at(7, walk, 100_000), direct);
+        assert_eq!(find_cycle(7, walk).equivalent_step(1), 1);
+        Ok(())
+    }
+}
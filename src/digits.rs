@@ -0,0 +1,260 @@
+//! Decimal digit helpers that work on the numbers directly, without going via strings.
+//! Zero has the single digit 0, and negative numbers aren't supported.
+
+use num::PrimInt;
+
+fn ten<T: PrimInt>() -> T {
+    T::from(10).unwrap()
+}
+
+/// 10 to the power `exponent`
+pub fn pow10<T: PrimInt>(exponent: u32) -> T {
+    ten::<T>().pow(exponent)
+}
+
+/// The number of decimal digits in `n`
+pub fn digit_count<T: PrimInt>(n: T) -> u32 {
+    let mut count = 1;
+    let mut rest = n / ten();
+    while !rest.is_zero() {
+        rest = rest / ten();
+        count += 1;
+    }
+    count
+}
+
+/// The digits of `n`, most significant first. Use `.rev()` for least significant first.
+pub fn digits<T: PrimInt>(n: T) -> Digits<T> {
+    let count = digit_count(n);
+    Digits {
+        value: n,
+        front: pow10(count - 1),
+        back: T::one(),
+        remaining: count,
+    }
+}
+
+/// An iterator over the digits of a number, from either end
+#[derive(Clone, Debug)]
+pub struct Digits<T> {
+    value: T,
+    // The place values of the next digit from each end
+    front: T,
+    back: T,
+    remaining: u32,
+}
+
+impl<T: PrimInt> Iterator for Digits<T> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let digit = self.value / self.front % ten();
+        self.front = self.front / ten();
+        self.remaining -= 1;
+        digit.to_u8()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining as usize, Some(self.remaining as usize))
+    }
+}
+
+impl<T: PrimInt> DoubleEndedIterator for Digits<T> {
+    fn next_back(&mut self) -> Option<u8> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let digit = self.value / self.back % ten();
+        self.remaining -= 1;
+        if self.remaining > 0 {
+            self.back = self.back * ten();
+        }
+        digit.to_u8()
+    }
+}
+
+impl<T: PrimInt> ExactSizeIterator for Digits<T> {}
+
+/// The number with these digits, most significant first, or `None` if it doesn't fit
+pub fn from_digits<T: PrimInt, D: PrimInt>(digits: impl IntoIterator<Item = D>) -> Option<T> {
+    digits.into_iter().try_fold(T::zero(), |acc, digit| {
+        acc.checked_mul(&ten())?.checked_add(&T::from(digit)?)
+    })
+}
+
+/// `n` split into the digits above the last `low_digits` and those last digits, so
+/// `split_at(123456, 2)` is `(1234, 56)`
+pub fn split_at<T: PrimInt>(n: T, low_digits: u32) -> (T, T) {
+    let unit = pow10(low_digits);
+    (n / unit, n % unit)
+}
+
+/// The two halves of a number with an even number of digits, so `halves(123456)` is
+/// `Some((123, 456))`
+pub fn halves<T: PrimInt>(n: T) -> Option<(T, T)> {
+    let count = digit_count(n);
+    count.is_multiple_of(2).then(|| split_at(n, count / 2))
+}
+
+/// The blocks of `size` digits making up `n`, most significant first, or `None` if the
+/// digits don't divide evenly. Blocks after the first may have leading zeros, so 1005
+/// in blocks of two is 10 and 5.
+pub fn blocks<T: PrimInt>(n: T, size: u32) -> Option<Blocks<T>> {
+    let count = digit_count(n);
+    (size > 0 && count.is_multiple_of(size)).then(|| Blocks {
+        value: n,
+        front: pow10(count - size),
+        // A single block is the whole number, and 10^size might not fit
+        unit: (size < count).then(|| pow10(size)),
+    })
+}
+
+/// An iterator over the fixed-size blocks of a number's digits
+#[derive(Clone, Debug)]
+pub struct Blocks<T> {
+    value: T,
+    // The place value of the next block, or zero once they've all been taken
+    front: T,
+    // The place value of one block, or `None` if there's only one
+    unit: Option<T>,
+}
+
+impl<T: PrimInt> Iterator for Blocks<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.front.is_zero() {
+            return None;
+        }
+        let Some(unit) = self.unit else {
+            self.front = T::zero();
+            return Some(self.value);
+        };
+        let block = self.value / self.front % unit;
+        self.front = self.front / unit;
+        Some(block)
+    }
+}
+
+/// `a` followed by the digits of `b`, so `concat(12, 345)` is 12345
+pub fn concat<T: PrimInt>(a: T, b: T) -> T {
+    a * pow10(digit_count(b)) + b
+}
+
+/// `block` written out `times` times, so `repeat(12, 3)` is 121212
+pub fn repeat<T: PrimInt>(block: T, times: u32) -> T {
+    if times <= 1 {
+        // Nothing to shift, and the place value of a second copy might not fit
+        return if times == 0 { T::zero() } else { block };
+    }
+    let unit = pow10(digit_count(block));
+    (1..times).fold(block, |acc, _| acc * unit + block)
+}
+
+/// The digits of `n` backwards, or `None` if that doesn't fit. Trailing zeros are
+/// lost, so `reverse(120)` is 21.
+pub fn reverse<T: PrimInt>(n: T) -> Option<T> {
+    from_digits(digits(n).rev())
+}
+
+/// The length of the shortest block that `n` is a repetition of, which is its digit
+/// count if it isn't a repetition at all
+pub fn period<T: PrimInt>(n: T) -> u32 {
+    let count = digit_count(n);
+    (1..count)
+        .filter(|size| count.is_multiple_of(*size))
+        // The digits have period `size` iff dropping the last block leaves the same
+        // number as dropping the first
+        .find(|size| n / pow10(*size) == n % pow10(count - size))
+        .unwrap_or(count)
+}
+
+/// Whether `n` is some block of digits repeated at least twice, like 1212 or 777
+pub fn is_periodic<T: PrimInt>(n: T) -> bool {
+    period(n) < digit_count(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    #[test]
+    fn test_digits() -> color_eyre::Result<()> {
+        assert_eq!(digit_count(0u32), 1);
+        assert_eq!(digit_count(9u8), 1);
+        assert_eq!(digit_count(10i64), 2);
+        assert_eq!(digit_count(u64::MAX), 20);
+        assert_eq!(digits(1203u32).collect_vec(), vec![1, 2, 0, 3]);
+        assert_eq!(digits(1203u32).rev().collect_vec(), vec![3, 0, 2, 1]);
+        assert_eq!(digits(0u32).collect_vec(), vec![0]);
+        assert_eq!(digits(u64::MAX).rev().count(), 20);
+        // Taking from both ends meets in the middle
+        let mut both = digits(12345u32);
+        assert_eq!((both.next(), both.next_back()), (Some(1), Some(5)));
+        assert_eq!(both.collect_vec(), vec![2, 3, 4]);
+        assert_eq!(from_digits::<u64, u8>(digits(u64::MAX)), Some(u64::MAX));
+        assert_eq!(from_digits::<u128, u128>(vec![9, 8, 7]), Some(987));
+        assert_eq!(from_digits::<u8, u8>(vec![2, 5, 6]), None);
+        assert_eq!(reverse(1230u32), Some(321));
+        assert_eq!(reverse(250u8), Some(52));
+        assert_eq!(reverse(255u8), None);
+        assert_eq!(reverse(u64::MAX), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_splitting_and_joining() -> color_eyre::Result<()> {
+        assert_eq!(split_at(123456u32, 2), (1234, 56));
+        assert_eq!(halves(123456u32), Some((123, 456)));
+        assert_eq!(halves(12345u32), None);
+        assert_eq!(blocks(100507u32, 2).unwrap().collect_vec(), vec![10, 5, 7]);
+        assert!(blocks(1005u32, 3).is_none());
+        assert_eq!(concat(12u32, 345), 12345);
+        assert_eq!(repeat(12u64, 3), 121212);
+        assert_eq!(repeat(7u8, 1), 7);
+        assert_eq!(repeat(7u8, 0), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_type_limits() -> color_eyre::Result<()> {
+        assert_eq!(blocks(u64::MAX, 20).unwrap().collect_vec(), vec![u64::MAX]);
+        assert_eq!(blocks(255u8, 3).unwrap().collect_vec(), vec![255]);
+        assert_eq!(
+            blocks(u64::MAX, 10).unwrap().collect_vec(),
+            vec![1844674407, 3709551615]
+        );
+        assert_eq!(halves(u64::MAX), Some((1844674407, 3709551615)));
+        assert_eq!(repeat(200u8, 1), 200);
+        assert_eq!(repeat(u64::MAX, 1), u64::MAX);
+        assert_eq!(repeat(1u64, 19), 1111111111111111111);
+        assert_eq!(concat(25u8, 5), 255);
+        assert_eq!(period(u64::MAX), 20);
+        Ok(())
+    }
+
+    #[test]
+    fn test_period() -> color_eyre::Result<()> {
+        assert_eq!(period(121212u32), 2);
+        assert_eq!(period(777u32), 1);
+        assert_eq!(period(1212121u32), 7);
+        assert_eq!(period(100100u32), 3);
+        assert_eq!(period(5u32), 1);
+        assert!(is_periodic(38593859u64));
+        assert!(!is_periodic(5u32));
+        assert!(!is_periodic(1010101u32));
+        // The same as comparing string chunks
+        for n in 0..20_000u32 {
+            let text = n.to_string();
+            let repeated = (1..text.len())
+                .filter(|size| text.len() % size == 0)
+                .any(|size| text.as_bytes().chunks(size).all_equal());
+            assert_eq!(is_periodic(n), repeated);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,572 @@
+use std::cmp::{Reverse, max, min};
+
+use itertools::Itertools;
+use num::integer::gcd;
+
+use crate::coordinate::Coordinate;
+
+/// A horizontal or vertical line segment, including both ends
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Segment {
+    pub start: Coordinate<i64>,
+    pub end: Coordinate<i64>,
+}
+
+/// An axis-aligned rectangle, including its edges
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rectangle {
+    pub min: Coordinate<i64>,
+    pub max: Coordinate<i64>,
+}
+
+/// A simple polygon whose edges are all horizontal or vertical, such as a loop of tiles.
+///
+/// The polygon runs through the vertices themselves, so for tiles it goes through their
+/// centres. It includes its boundary.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Polygon {
+    vertices: Vec<Coordinate<i64>>,
+}
+
+impl Segment {
+    pub fn new(start: Coordinate<i64>, end: Coordinate<i64>) -> Segment {
+        assert!(
+            start.row == end.row || start.col == end.col,
+            "segment from {:?} to {:?} isn't axis-aligned",
+            start,
+            end
+        );
+        Segment { start, end }
+    }
+
+    pub fn is_horizontal(&self) -> bool {
+        self.start.row == self.end.row
+    }
+
+    /// The smallest rectangle containing the segment, which is the segment itself
+    pub fn bounds(&self) -> Rectangle {
+        Rectangle::from_corners(self.start, self.end)
+    }
+
+    pub fn contains(&self, point: &Coordinate<i64>) -> bool {
+        self.bounds().contains(point)
+    }
+
+    /// Whether the segments share at least one point
+    pub fn intersects(&self, other: &Segment) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// The points the segments share: a single point where they cross, or a segment
+    /// where they overlap along the same line
+    pub fn intersection(&self, other: &Segment) -> Option<Segment> {
+        let a = self.bounds();
+        let b = other.bounds();
+        let lower = Coordinate::new(max(a.min.row, b.min.row), max(a.min.col, b.min.col));
+        let upper = Coordinate::new(min(a.max.row, b.max.row), min(a.max.col, b.max.col));
+        (lower.row <= upper.row && lower.col <= upper.col).then_some(Segment {
+            start: lower,
+            end: upper,
+        })
+    }
+}
+
+impl Rectangle {
+    /// The rectangle with opposite corners `a` and `b`
+    pub fn from_corners(a: Coordinate<i64>, b: Coordinate<i64>) -> Rectangle {
+        Rectangle {
+            min: Coordinate::new(min(a.row, b.row), min(a.col, b.col)),
+            max: Coordinate::new(max(a.row, b.row), max(a.col, b.col)),
+        }
+    }
+
+    pub fn width(&self) -> i64 {
+        self.max.col - self.min.col
+    }
+
+    pub fn height(&self) -> i64 {
+        self.max.row - self.min.row
+    }
+
+    /// The number of lattice points in the rectangle, i.e. the tiles it covers
+    pub fn tile_count(&self) -> i64 {
+        (self.width() + 1) * (self.height() + 1)
+    }
+
+    pub fn contains(&self, point: &Coordinate<i64>) -> bool {
+        self.min.row <= point.row
+            && point.row <= self.max.row
+            && self.min.col <= point.col
+            && point.col <= self.max.col
+    }
+
+    // Whether the segment passes through the rectangle without just touching its edges
+    fn crosses_interior(&self, segment: &Segment) -> bool {
+        let s = segment.bounds();
+        s.min.row < self.max.row
+            && s.max.row > self.min.row
+            && s.min.col < self.max.col
+            && s.max.col > self.min.col
+    }
+}
+
+impl Polygon {
+    /// The polygon through `vertices` in order, closing back to the first
+    pub fn new(vertices: Vec<Coordinate<i64>>) -> Polygon {
+        assert!(vertices.len() >= 2, "a polygon needs at least two vertices");
+        assert!(
+            vertices
+                .iter()
+                .circular_tuple_windows()
+                .all(|(a, b)| a.row == b.row || a.col == b.col),
+            "every edge of the polygon must be axis-aligned"
+        );
+        Polygon { vertices }
+    }
+
+    pub fn vertices(&self) -> &[Coordinate<i64>] {
+        &self.vertices
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = Segment> + '_ {
+        self.vertices
+            .iter()
+            .circular_tuple_windows()
+            .map(|(a, b)| Segment::new(*a, *b))
+    }
+
+    pub fn on_boundary(&self, point: &Coordinate<i64>) -> bool {
+        self.edges().any(|edge| edge.contains(point))
+    }
+
+    /// Whether `point` is inside or on the boundary
+    pub fn contains(&self, point: &Coordinate<i64>) -> bool {
+        self.contains_doubled(point.row * 2, point.col * 2)
+    }
+
+    /// Whether the whole rectangle is inside or on the boundary, in O(edges)
+    pub fn contains_rectangle(&self, rectangle: &Rectangle) -> bool {
+        if rectangle.width() == 0 || rectangle.height() == 0 {
+            return self.contains_segment(&Segment::new(rectangle.min, rectangle.max));
+        }
+        // If no edge passes through the inside of the rectangle, the inside is entirely
+        // in or entirely out of the polygon, so testing its centre settles it
+        !self.edges().any(|edge| rectangle.crosses_interior(&edge))
+            && self.contains_doubled(
+                rectangle.min.row + rectangle.max.row,
+                rectangle.min.col + rectangle.max.col,
+            )
+    }
+
+    /// Twice the enclosed area, which is always a whole number
+    pub fn double_area(&self) -> u128 {
+        double_area(&self.vertices)
+    }
+
+    /// The length of the boundary
+    pub fn perimeter(&self) -> u128 {
+        manhattan_perimeter(&self.vertices)
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        orientation(&self.vertices)
+    }
+
+    /// The number of lattice points inside or on the boundary, i.e. the tiles covered
+    /// by a loop of tiles and everything it encloses.
+    ///
+    /// By Pick's theorem that's `A + B / 2 + 1`, which also counts a loop that just goes
+    /// out and back along a line correctly, since it passes every point twice.
+    pub fn tile_count(&self) -> u128 {
+        (self.double_area() + boundary_point_count(&self.vertices)) / 2 + 1
+    }
+
+    // A zero-width rectangle can't be checked by its centre, so split it wherever an
+    // edge meets it and check the middle of each piece. This is O(edges) per piece.
+    fn contains_segment(&self, segment: &Segment) -> bool {
+        let (along, start, end): (fn(&Coordinate<i64>) -> i64, _, _) = if segment.is_horizontal() {
+            (|c| c.col, segment.start.col, segment.end.col)
+        } else {
+            (|c| c.row, segment.start.row, segment.end.row)
+        };
+        let (start, end) = (min(start, end), max(start, end));
+        let line = Segment::new(segment.start, segment.end);
+        let cuts = self
+            .edges()
+            .filter_map(|edge| edge.intersection(&line))
+            .flat_map(|shared| [along(&shared.start), along(&shared.end)])
+            .chain([start, end])
+            .sorted()
+            .dedup()
+            .collect_vec();
+        let point_at = |doubled: i64| {
+            if segment.is_horizontal() {
+                (segment.start.row * 2, doubled)
+            } else {
+                (doubled, segment.start.col * 2)
+            }
+        };
+        cuts.iter().all(|cut| {
+            let (row, col) = point_at(cut * 2);
+            self.contains_doubled(row, col)
+        }) && cuts.iter().tuple_windows().all(|(a, b)| {
+            let (row, col) = point_at(a + b);
+            self.contains_doubled(row, col)
+        })
+    }
+
+    // Point in polygon with every coordinate doubled, so that points halfway between
+    // lattice points can be tested exactly
+    fn contains_doubled(&self, row: i64, col: i64) -> bool {
+        let mut inside = false;
+        for edge in self.edges() {
+            let b = edge.bounds();
+            let (min_row, max_row) = (b.min.row * 2, b.max.row * 2);
+            let (min_col, max_col) = (b.min.col * 2, b.max.col * 2);
+            if min_row <= row && row <= max_row && min_col <= col && col <= max_col {
+                return true;
+            }
+            // Cast a ray towards increasing columns, counting the vertical edges it
+            // crosses. Each edge is half-open so a ray through a vertex counts once.
+            if !edge.is_horizontal() && min_col > col && min_row <= row && row < max_row {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+}
+
+/// Which way a loop of vertices turns, as drawn with row 0 at the top
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    Clockwise,
+    CounterClockwise,
+    /// The loop encloses no area
+    Degenerate,
+}
+
+/// Twice the signed area of the loop by the shoelace formula. It's positive when the
+/// loop runs clockwise with row 0 at the top.
+pub fn signed_double_area(vertices: &[Coordinate<i64>]) -> i128 {
+    vertices
+        .iter()
+        .circular_tuple_windows()
+        .map(|(a, b)| a.col as i128 * b.row as i128 - b.col as i128 * a.row as i128)
+        .sum()
+}
+
+/// Twice the area enclosed by the loop, which is always a whole number for lattice points
+pub fn double_area(vertices: &[Coordinate<i64>]) -> u128 {
+    signed_double_area(vertices).unsigned_abs()
+}
+
+pub fn orientation(vertices: &[Coordinate<i64>]) -> Orientation {
+    match signed_double_area(vertices).signum() {
+        1 => Orientation::Clockwise,
+        -1 => Orientation::CounterClockwise,
+        _ => Orientation::Degenerate,
+    }
+}
+
+/// The length of the loop moving only along rows and columns. That's the true perimeter
+/// when every edge is horizontal or vertical.
+pub fn manhattan_perimeter(vertices: &[Coordinate<i64>]) -> u128 {
+    vertices
+        .iter()
+        .circular_tuple_windows()
+        .map(|(a, b)| a.row.abs_diff(b.row) as u128 + a.col.abs_diff(b.col) as u128)
+        .sum()
+}
+
+/// The number of lattice points on the loop. An edge with steps `dr` and `dc` passes
+/// through `gcd(dr, dc)` of them, not counting its start.
+pub fn boundary_point_count(vertices: &[Coordinate<i64>]) -> u128 {
+    vertices
+        .iter()
+        .circular_tuple_windows()
+        .map(|(a, b)| gcd(a.row.abs_diff(b.row) as u128, a.col.abs_diff(b.col) as u128))
+        .sum()
+}
+
+/// The number of lattice points strictly inside a simple loop, by Pick's theorem:
+/// `2A = 2I + B - 2`. A loop enclosing no area has nothing inside, even though it
+/// passes its points twice and so has a longer boundary than the theorem allows for.
+pub fn interior_point_count(vertices: &[Coordinate<i64>]) -> u128 {
+    (double_area(vertices) + 2).saturating_sub(boundary_point_count(vertices)) / 2
+}
+
+/// The rectangle covering the most tiles that has two of `corners` as opposite corners
+/// and that `allowed` accepts.
+///
+/// No rectangle can reach past the bounding box of all the corners, so each corner's
+/// best possible rectangle is the one to the far corner of that box. Corners are tried
+/// in order of that bound, and the search stops as soon as none left can beat the best
+/// found. For each corner only partners bigger than the best so far are checked, biggest
+/// first, so `allowed` is only asked about rectangles that would improve on it.
+/// Memory is O(corners).
+pub fn largest_rectangle(
+    corners: &[Coordinate<i64>],
+    mut allowed: impl FnMut(&Rectangle) -> bool,
+) -> Option<Rectangle> {
+    let rows = corners.iter().map(|c| c.row).minmax().into_option()?;
+    let cols = corners.iter().map(|c| c.col).minmax().into_option()?;
+    let reach = |c: &Coordinate<i64>| {
+        // The gaps to either edge can be bigger than an i64 holds
+        let row = if c.row.abs_diff(rows.0) > c.row.abs_diff(rows.1) {
+            rows.0
+        } else {
+            rows.1
+        };
+        let col = if c.col.abs_diff(cols.0) > c.col.abs_diff(cols.1) {
+            cols.0
+        } else {
+            cols.1
+        };
+        Rectangle::from_corners(*c, Coordinate::new(row, col)).tile_count()
+    };
+    let order = (0..corners.len())
+        .map(|a| (reach(&corners[a]), a))
+        .sorted_unstable_by(|x, y| y.cmp(x))
+        .collect_vec();
+    let mut best: Option<(i64, Rectangle)> = None;
+    let mut done = vec![false; corners.len()];
+    let mut candidates = vec![];
+    for (bound, a) in order {
+        let beaten = |size: i64| best.is_none_or(|(best_size, _)| size > best_size);
+        if !beaten(bound) {
+            break;
+        }
+        done[a] = true;
+        // Pairs with a corner already done were checked from its side
+        candidates.clear();
+        candidates.extend(
+            (0..corners.len())
+                .filter(|b| !done[*b])
+                .map(|b| (Rectangle::from_corners(corners[a], corners[b]), b))
+                .filter(|(rectangle, _)| beaten(rectangle.tile_count())),
+        );
+        candidates.sort_unstable_by_key(|(rectangle, b)| (Reverse(rectangle.tile_count()), *b));
+        if let Some((rectangle, _)) = candidates.iter().find(|(rectangle, _)| allowed(rectangle)) {
+            best = Some((rectangle.tile_count(), *rectangle));
+        }
+    }
+    best.map(|(_, rectangle)| rectangle)
+}
+
+impl Polygon {
+    /// The largest rectangle inside the polygon with two vertices as opposite corners
+    pub fn largest_rectangle(&self) -> Option<Rectangle> {
+        largest_rectangle(&self.vertices, |r| self.contains_rectangle(r))
+    }
+}
+
+/// The largest all-`true` rectangle in a grid, in row and column indices, using the
+/// histogram stack method in O(rows * cols)
+pub fn largest_rectangle_in_grid(grid: &[Vec<bool>]) -> Option<Rectangle> {
+    let cols = grid.first().map_or(0, |row| row.len());
+    // How far up from each cell the run of trues goes, including the cell
+    let mut heights = vec![0; cols];
+    let mut best: Option<(usize, Rectangle)> = None;
+    for (row, cells) in grid.iter().enumerate() {
+        for (height, cell) in heights.iter_mut().zip(cells) {
+            *height = if *cell { *height + 1 } else { 0 };
+        }
+        // Columns with increasing heights. When a shorter column arrives, every taller
+        // one on the stack can't extend any further right, so its rectangle is done.
+        let mut stack: Vec<usize> = vec![];
+        for col in 0..=cols {
+            let height = heights.get(col).copied().unwrap_or(0);
+            while let Some(&top) = stack.last() {
+                if heights[top] < height {
+                    break;
+                }
+                stack.pop();
+                let left = stack.last().map_or(0, |l| l + 1);
+                let area = heights[top] * (col - left);
+                if area > 0 && best.as_ref().is_none_or(|(b, _)| area > *b) {
+                    let min = Coordinate::new((row + 1 - heights[top]) as i64, left as i64);
+                    let max = Coordinate::new(row as i64, col as i64 - 1);
+                    best = Some((area, Rectangle::from_corners(min, max)));
+                }
+            }
+            stack.push(col);
+        }
+    }
+    best.map(|(_, rectangle)| rectangle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loop_of(points: &[(i64, i64)]) -> Vec<Coordinate<i64>> {
+        points.iter().map(|p| Coordinate::from(*p)).collect()
+    }
+
+    fn polygon(points: &[(i64, i64)]) -> Polygon {
+        Polygon::new(points.iter().map(|p| Coordinate::from(*p)).collect())
+    }
+
+    #[test]
+    fn test_segment_intersection() -> color_eyre::Result<()> {
+        let horizontal = Segment::new((2, 0).into(), (2, 10).into());
+        let vertical = Segment::new((0, 5).into(), (8, 5).into());
+        assert_eq!(
+            horizontal.intersection(&vertical),
+            Some(Segment::new((2, 5).into(), (2, 5).into()))
+        );
+        let overlapping = Segment::new((2, 8).into(), (2, 20).into());
+        assert_eq!(
+            horizontal.intersection(&overlapping),
+            Some(Segment::new((2, 8).into(), (2, 10).into()))
+        );
+        assert!(!vertical.intersects(&Segment::new((9, 0).into(), (9, 10).into())));
+        Ok(())
+    }
+
+    #[test]
+    fn test_contains_point() -> color_eyre::Result<()> {
+        // An L shape, missing the top right corner
+        let shape = polygon(&[(0, 0), (0, 4), (2, 4), (2, 8), (6, 8), (6, 0)]);
+        assert!(shape.contains(&(0, 0).into()));
+        assert!(shape.contains(&(0, 2).into()));
+        assert!(shape.contains(&(3, 3).into()));
+        assert!(shape.contains(&(2, 6).into()));
+        assert!(!shape.contains(&(1, 6).into()));
+        assert!(!shape.contains(&(7, 3).into()));
+        // Level with the vertices at the inner corner
+        assert!(shape.contains(&(2, 1).into()));
+        assert!(!shape.contains(&(2, -1).into()));
+        assert!(shape.on_boundary(&(4, 8).into()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_contains_rectangle() -> color_eyre::Result<()> {
+        // A U shape: the gap between the arms is outside
+        let shape = polygon(&[
+            (0, 0),
+            (0, 2),
+            (4, 2),
+            (4, 6),
+            (0, 6),
+            (0, 8),
+            (6, 8),
+            (6, 0),
+        ]);
+        let rectangle = |a: (i64, i64), b: (i64, i64)| Rectangle::from_corners(a.into(), b.into());
+        assert!(shape.contains_rectangle(&rectangle((0, 0), (6, 2))));
+        assert!(shape.contains_rectangle(&rectangle((4, 0), (6, 8))));
+        // Every corner is inside, but the middle crosses the gap
+        assert!(!shape.contains_rectangle(&rectangle((0, 0), (6, 8))));
+        assert!(!shape.contains_rectangle(&rectangle((1, 3), (3, 5))));
+        // Lines along the boundary, and across the gap
+        assert!(shape.contains_rectangle(&rectangle((4, 2), (4, 6))));
+        assert!(!shape.contains_rectangle(&rectangle((2, 0), (2, 8))));
+        assert!(shape.contains_rectangle(&rectangle((5, 5), (5, 5))));
+        assert_eq!(rectangle((0, 0), (6, 2)).tile_count(), 21);
+        Ok(())
+    }
+
+    #[test]
+    fn test_area_and_lattice_points() -> color_eyre::Result<()> {
+        let square = polygon(&[(0, 0), (0, 4), (4, 4), (4, 0)]);
+        assert_eq!(square.double_area(), 32);
+        assert_eq!(square.perimeter(), 16);
+        assert_eq!(square.tile_count(), 25);
+        assert_eq!(square.orientation(), Orientation::Clockwise);
+        let triangle = loop_of(&[(0, 0), (3, 0), (0, 3)]);
+        assert_eq!(signed_double_area(&triangle), -9);
+        assert_eq!(orientation(&triangle), Orientation::CounterClockwise);
+        assert_eq!(boundary_point_count(&triangle), 9);
+        assert_eq!(interior_point_count(&triangle), 1);
+        assert_eq!(
+            orientation(&loop_of(&[(0, 0), (0, 5)])),
+            Orientation::Degenerate
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_degenerate_loops() -> color_eyre::Result<()> {
+        let line = polygon(&[(0, 0), (0, 5)]);
+        assert_eq!(interior_point_count(line.vertices()), 0);
+        assert_eq!(line.tile_count(), 6);
+        // Out along a row, down a column and back the same way
+        let corner = polygon(&[(0, 0), (0, 3), (2, 3), (0, 3)]);
+        assert_eq!(corner.double_area(), 0);
+        assert_eq!(interior_point_count(corner.vertices()), 0);
+        assert_eq!(corner.tile_count(), 6);
+        assert_eq!(polygon(&[(1, 1), (1, 1)]).tile_count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_largest_rectangle() -> color_eyre::Result<()> {
+        let shape = polygon(&[
+            (0, 0),
+            (0, 2),
+            (4, 2),
+            (4, 6),
+            (0, 6),
+            (0, 8),
+            (6, 8),
+            (6, 0),
+        ]);
+        let best = shape.largest_rectangle().unwrap();
+        assert_eq!(best.tile_count(), 21);
+        assert!(shape.contains_rectangle(&best));
+        let unconstrained = largest_rectangle(shape.vertices(), |_| true).unwrap();
+        assert_eq!(unconstrained.tile_count(), 63);
+        assert_eq!(largest_rectangle(shape.vertices(), |_| false), None);
+        assert_eq!(largest_rectangle(&[], |_| true), None);
+        // The best corners come first here, so nothing smaller than the answer is checked
+        let mut checked = vec![];
+        let best = largest_rectangle(shape.vertices(), |r| {
+            checked.push(r.tile_count());
+            shape.contains_rectangle(r)
+        });
+        assert_eq!(best.map(|r| r.tile_count()), Some(21));
+        assert!(checked.iter().all(|size| *size >= 21));
+        assert_eq!(checked.iter().filter(|size| **size == 21).count(), 1);
+        // The same answer as checking every pair
+        let points = (0..30)
+            .map(|i: i64| Coordinate::new((i * 37) % 23, (i * 53) % 19))
+            .collect_vec();
+        let brute = points
+            .iter()
+            .tuple_combinations()
+            .map(|(a, b)| Rectangle::from_corners(*a, *b))
+            .filter(|r| r.width() % 3 != 0)
+            .map(|r| r.tile_count())
+            .max();
+        let found = largest_rectangle(&points, |r| r.width() % 3 != 0);
+        assert_eq!(found.map(|r| r.tile_count()), brute);
+        Ok(())
+    }
+
+    #[test]
+    fn test_largest_rectangle_in_grid() -> color_eyre::Result<()> {
+        let grid = [".##..", "####.", "####.", ".###."]
+            .iter()
+            .map(|row| row.chars().map(|c| c == '#').collect_vec())
+            .collect_vec();
+        let best = largest_rectangle_in_grid(&grid).unwrap();
+        assert_eq!(best, Rectangle::from_corners((1, 1).into(), (3, 3).into()));
+        // The same as checking every rectangle
+        let brute = (0..4)
+            .cartesian_product(0..5)
+            .tuple_combinations()
+            .map(|(a, b)| Rectangle::from_corners(a.into(), b.into()))
+            .filter(|r| {
+                (r.min.row..=r.max.row)
+                    .cartesian_product(r.min.col..=r.max.col)
+                    .all(|(row, col)| grid[row as usize][col as usize])
+            })
+            .map(|r| r.tile_count())
+            .max();
+        assert_eq!(Some(best.tile_count()), brute);
+        assert_eq!(largest_rectangle_in_grid(&[vec![false; 3]]), None);
+        Ok(())
+    }
+}
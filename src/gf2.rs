@@ -0,0 +1,415 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::ops::{BitXor, BitXorAssign};
+
+use crate::bitset::BitSet;
+
+/// A vector over GF(2), packed 64 bits to a word. Addition is XOR.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BitVector {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitVector {
+    /// The zero vector of length `len`
+    pub fn new(len: usize) -> BitVector {
+        BitVector {
+            words: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    pub fn from_bools(bits: &[bool]) -> BitVector {
+        let mut vector = BitVector::new(bits.len());
+        for (i, bit) in bits.iter().enumerate() {
+            vector.set(i, *bit);
+        }
+        vector
+    }
+
+    /// A vector of length `len` with a one at each of `indices`
+    pub fn from_indices(len: usize, indices: &[usize]) -> BitVector {
+        let mut vector = BitVector::new(len);
+        for i in indices {
+            vector.set(*i, true);
+        }
+        vector
+    }
+
+    /// A vector of length `len` with a one at each index in `set`, copied a word at a time
+    pub fn from_bitset(set: BitSet, len: usize) -> BitVector {
+        assert!(
+            len >= BitSet::CAPACITY || set.bits() >> len == 0,
+            "set {:?} has indices out of range for length {}",
+            set,
+            len
+        );
+        let mut vector = BitVector::new(len);
+        for (i, word) in vector.words.iter_mut().take(2).enumerate() {
+            *word = (set.bits() >> (64 * i)) as u64;
+        }
+        vector
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        assert!(
+            i < self.len,
+            "bit {} out of range for length {}",
+            i,
+            self.len
+        );
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    pub fn set(&mut self, i: usize, value: bool) {
+        assert!(
+            i < self.len,
+            "bit {} out of range for length {}",
+            i,
+            self.len
+        );
+        if value {
+            self.words[i / 64] |= 1 << (i % 64);
+        } else {
+            self.words[i / 64] &= !(1 << (i % 64));
+        }
+    }
+
+    pub fn flip(&mut self, i: usize) {
+        let value = self.get(i);
+        self.set(i, !value);
+    }
+
+    /// The number of ones, i.e. the Hamming weight
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.words.iter().all(|w| *w == 0)
+    }
+
+    /// The indices of every one, in ascending order
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len).filter(|i| self.get(*i))
+    }
+
+    /// The dot product, which is the parity of the ones the two vectors share
+    pub fn dot(&self, other: &BitVector) -> bool {
+        self.words
+            .iter()
+            .zip(&other.words)
+            .map(|(a, b)| (a & b).count_ones())
+            .sum::<u32>()
+            % 2
+            == 1
+    }
+}
+
+impl BitXorAssign<&BitVector> for BitVector {
+    fn bitxor_assign(&mut self, rhs: &BitVector) {
+        assert_eq!(self.len, rhs.len, "vectors must be the same length");
+        for (a, b) in self.words.iter_mut().zip(&rhs.words) {
+            *a ^= b;
+        }
+    }
+}
+
+impl BitXor<&BitVector> for &BitVector {
+    type Output = BitVector;
+
+    fn bitxor(self, rhs: &BitVector) -> BitVector {
+        let mut result = self.clone();
+        result ^= rhs;
+        result
+    }
+}
+
+/// A matrix over GF(2), stored as bit-packed rows
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Matrix {
+    rows: Vec<BitVector>,
+    cols: usize,
+}
+
+/// Every solution of `Ax = b`: `particular` plus any sum of vectors from `null_space`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solutions {
+    pub particular: BitVector,
+    pub null_space: Vec<BitVector>,
+}
+
+/// The most free variables `min_weight_solution` will enumerate, so a `u64` can count
+/// through every combination
+pub const MAX_FREE_VARIABLES: usize = 63;
+
+/// A system had more free variables than `min_weight_solution` can try
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TooManyFreeVariables {
+    pub count: usize,
+}
+
+impl Display for TooManyFreeVariables {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} free variables is more than the {} that can be enumerated",
+            self.count, MAX_FREE_VARIABLES
+        )
+    }
+}
+
+impl Error for TooManyFreeVariables {}
+
+impl Matrix {
+    /// The zero matrix
+    pub fn new(rows: usize, cols: usize) -> Matrix {
+        Matrix {
+            rows: vec![BitVector::new(cols); rows],
+            cols,
+        }
+    }
+
+    pub fn from_rows(rows: Vec<BitVector>) -> Matrix {
+        let cols = rows.first().map_or(0, |r| r.len());
+        assert!(
+            rows.iter().all(|r| r.len() == cols),
+            "rows must be the same length"
+        );
+        Matrix { rows, cols }
+    }
+
+    /// Build a matrix from its columns. For a toggle puzzle each column is one button,
+    /// and each row one light.
+    pub fn from_columns(columns: &[BitVector], rows: usize) -> Matrix {
+        let mut matrix = Matrix::new(rows, columns.len());
+        for (col, column) in columns.iter().enumerate() {
+            assert_eq!(column.len(), rows, "columns must have one bit per row");
+            for row in column.iter_ones() {
+                matrix.set(row, col, true);
+            }
+        }
+        matrix
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn col_count(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> bool {
+        self.rows[row].get(col)
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: bool) {
+        self.rows[row].set(col, value)
+    }
+
+    pub fn mul_vector(&self, x: &BitVector) -> BitVector {
+        assert_eq!(x.len(), self.cols, "vector must have one bit per column");
+        let bits = self.rows.iter().map(|row| row.dot(x)).collect::<Vec<_>>();
+        BitVector::from_bools(&bits)
+    }
+
+    pub fn rank(&self) -> usize {
+        let mut rows = self.rows.clone();
+        let mut rhs = vec![false; rows.len()];
+        reduce(&mut rows, &mut rhs, self.cols).len()
+    }
+
+    /// A basis for the vectors `x` with `Ax = 0`
+    pub fn null_space(&self) -> Vec<BitVector> {
+        let mut rows = self.rows.clone();
+        let mut rhs = vec![false; rows.len()];
+        let pivots = reduce(&mut rows, &mut rhs, self.cols);
+        null_space(&rows, &pivots, self.cols)
+    }
+
+    /// Every solution of `Ax = b` by Gaussian elimination, or `None` if there aren't any
+    pub fn solve(&self, b: &BitVector) -> Option<Solutions> {
+        assert_eq!(b.len(), self.rows.len(), "b must have one bit per row");
+        let mut rows = self.rows.clone();
+        let mut rhs = (0..b.len()).map(|i| b.get(i)).collect::<Vec<_>>();
+        let pivots = reduce(&mut rows, &mut rhs, self.cols);
+        // Rows without a pivot are all zero, so they can only be satisfied by a zero
+        if rhs[pivots.len()..].iter().any(|bit| *bit) {
+            return None;
+        }
+        let mut particular = BitVector::new(self.cols);
+        for (row, col) in pivots.iter().enumerate() {
+            particular.set(*col, rhs[row]);
+        }
+        Some(Solutions {
+            particular,
+            null_space: null_space(&rows, &pivots, self.cols),
+        })
+    }
+
+    /// The solution of `Ax = b` with the fewest ones, e.g. the fewest button presses.
+    ///
+    /// This tries every combination of free variables, so it takes O(2^free) steps
+    /// where `free` is the dimension of the null space, and gives up with an error if
+    /// that's more than `MAX_FREE_VARIABLES`. `None` if there's no solution at all.
+    pub fn min_weight_solution(
+        &self,
+        b: &BitVector,
+    ) -> Result<Option<BitVector>, TooManyFreeVariables> {
+        let Some(solutions) = self.solve(b) else {
+            return Ok(None);
+        };
+        let count = solutions.null_space.len();
+        if count > MAX_FREE_VARIABLES {
+            return Err(TooManyFreeVariables { count });
+        }
+        let mut current = solutions.particular.clone();
+        let mut best = current.clone();
+        // Walk a Gray code, so each step toggles exactly one basis vector in or out
+        for step in 1u64..(1 << count) {
+            current ^= &solutions.null_space[step.trailing_zeros() as usize];
+            if current.count_ones() < best.count_ones() {
+                best = current.clone();
+            }
+        }
+        Ok(Some(best))
+    }
+}
+
+// Reduce rows to reduced row echelon form, applying the same operations to rhs.
+// Returns the pivot column of each of the first rows, which are the non-zero ones.
+fn reduce(rows: &mut [BitVector], rhs: &mut [bool], cols: usize) -> Vec<usize> {
+    let mut pivots = vec![];
+    for col in 0..cols {
+        let rank = pivots.len();
+        let Some(pivot) = (rank..rows.len()).find(|r| rows[*r].get(col)) else {
+            continue;
+        };
+        rows.swap(rank, pivot);
+        rhs.swap(rank, pivot);
+        let (before, after) = rows.split_at_mut(rank);
+        let (pivot_row, after) = after.split_first_mut().unwrap();
+        for (r, row) in before.iter_mut().enumerate() {
+            if row.get(col) {
+                *row ^= pivot_row;
+                rhs[r] ^= rhs[rank];
+            }
+        }
+        for (r, row) in after.iter_mut().enumerate() {
+            if row.get(col) {
+                *row ^= pivot_row;
+                rhs[rank + 1 + r] ^= rhs[rank];
+            }
+        }
+        pivots.push(col);
+    }
+    pivots
+}
+
+// One basis vector per free column, setting that column and solving for the pivots
+fn null_space(rows: &[BitVector], pivots: &[usize], cols: usize) -> Vec<BitVector> {
+    (0..cols)
+        .filter(|col| !pivots.contains(col))
+        .map(|free| {
+            let mut vector = BitVector::new(cols);
+            vector.set(free, true);
+            for (row, pivot) in pivots.iter().enumerate() {
+                vector.set(*pivot, rows[row].get(free));
+            }
+            vector
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_vector() -> color_eyre::Result<()> {
+        let mut a = BitVector::from_indices(130, &[0, 64, 129]);
+        let b = BitVector::from_indices(130, &[64, 100]);
+        assert_eq!(a.count_ones(), 3);
+        assert!(a.dot(&b));
+        a ^= &b;
+        assert_eq!(a.iter_ones().collect::<Vec<_>>(), vec![0, 100, 129]);
+        a.flip(0);
+        assert!(!a.get(0));
+        assert!((&a ^ &a).is_zero());
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_bitset() -> color_eyre::Result<()> {
+        let set = [0, 63, 64, 127].into_iter().collect::<BitSet>();
+        let vector = BitVector::from_bitset(set, 130);
+        assert_eq!(vector.iter_ones().collect::<Vec<_>>(), vec![0, 63, 64, 127]);
+        assert_eq!(vector, BitVector::from_indices(130, &[0, 63, 64, 127]));
+        let short = BitVector::from_bitset(BitSet::from_bits(0b101), 3);
+        assert_eq!(short, BitVector::from_bools(&[true, false, true]));
+        assert_eq!(BitVector::from_bitset(BitSet::new(), 0), BitVector::new(0));
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range for length 2")]
+    fn test_from_bitset_out_of_range() {
+        BitVector::from_bitset(BitSet::from_bits(0b100), 2);
+    }
+
+    #[test]
+    fn test_rank_and_null_space() -> color_eyre::Result<()> {
+        let matrix = Matrix::from_rows(vec![
+            BitVector::from_bools(&[true, true, false]),
+            BitVector::from_bools(&[false, true, true]),
+            BitVector::from_bools(&[true, false, true]),
+        ]);
+        assert_eq!(matrix.rank(), 2);
+        let null_space = matrix.null_space();
+        assert_eq!(null_space, vec![BitVector::from_bools(&[true, true, true])]);
+        assert!(matrix.mul_vector(&null_space[0]).is_zero());
+        Ok(())
+    }
+
+    #[test]
+    fn test_min_weight_solution() -> color_eyre::Result<()> {
+        // 65 unit columns and an all-ones column, so rows span two words. All ones can
+        // be made from every unit column, but the lightest solution is the last column.
+        let mut columns = (0..65)
+            .map(|i| BitVector::from_indices(65, &[i]))
+            .collect::<Vec<_>>();
+        columns.push(BitVector::from_bools(&[true; 65]));
+        let matrix = Matrix::from_columns(&columns, 65);
+        let target = BitVector::from_bools(&[true; 65]);
+        assert_eq!(matrix.solve(&target).unwrap().null_space.len(), 1);
+        let presses = matrix.min_weight_solution(&target)?.unwrap();
+        assert_eq!(presses, BitVector::from_indices(66, &[65]));
+        assert_eq!(matrix.mul_vector(&presses), target);
+        // Two identical rows can't have different values
+        let matrix = Matrix::from_rows(vec![BitVector::from_bools(&[true]); 2]);
+        assert_eq!(matrix.solve(&BitVector::from_bools(&[true, false])), None);
+        assert_eq!(
+            matrix.min_weight_solution(&BitVector::from_bools(&[true, false])),
+            Ok(None)
+        );
+        // A single zero row leaves every column free
+        let matrix = Matrix::from_rows(vec![BitVector::new(MAX_FREE_VARIABLES + 1)]);
+        assert_eq!(
+            matrix.min_weight_solution(&BitVector::new(1)),
+            Err(TooManyFreeVariables {
+                count: MAX_FREE_VARIABLES + 1
+            })
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
+
+use nom::{
+    Finish, IResult,
+    bytes::complete::{is_not, tag},
+    character::complete::space1,
+    combinator::all_consuming,
+    multi::many0,
+    sequence::{preceded, separated_pair},
+};
+use petgraph::{
+    Direction,
+    graph::{DiGraph, Neighbors, NodeIndex},
+};
+
+/// A directed graph whose nodes are identified by names borrowed from the input.
+///
+/// Each node's weight is its name, so index -> name is a graph lookup, and a
+/// map is kept alongside for name -> index. Both directions are O(1).
+#[derive(Debug, Clone, Default)]
+pub struct NamedGraph<'a> {
+    graph: DiGraph<&'a str, ()>,
+    indices: HashMap<&'a str, NodeIndex>,
+}
+
+impl<'a> NamedGraph<'a> {
+    pub fn new() -> NamedGraph<'a> {
+        NamedGraph::default()
+    }
+
+    /// Parse lines of the form `name: a b c`, with an edge from `name` to each of `a`, `b` and `c`
+    pub fn from_adjacency_list(
+        input: &'a str,
+    ) -> Result<NamedGraph<'a>, nom::error::Error<&'a str>> {
+        let mut graph = NamedGraph::new();
+        for line in input.lines().filter(|line| !line.trim().is_empty()) {
+            let (_, (source, targets)) =
+                all_consuming(parse_adjacency)(line.trim_end()).finish()?;
+            let source = graph.add_node(source);
+            for target in targets {
+                let target = graph.add_node(target);
+                graph.graph.add_edge(source, target, ());
+            }
+        }
+        Ok(graph)
+    }
+
+    /// Get the index for `name`, adding a new node if it hasn't been seen before
+    pub fn add_node(&mut self, name: &'a str) -> NodeIndex {
+        *self
+            .indices
+            .entry(name)
+            .or_insert_with(|| self.graph.add_node(name))
+    }
+
+    /// Add an edge between two names, adding either node if needed
+    pub fn add_edge(&mut self, source: &'a str, target: &'a str) {
+        let source = self.add_node(source);
+        let target = self.add_node(target);
+        self.graph.add_edge(source, target, ());
+    }
+
+    pub fn index(&self, name: &str) -> Option<NodeIndex> {
+        self.indices.get(name).copied()
+    }
+
+    pub fn name(&self, index: NodeIndex) -> &'a str {
+        self.graph[index]
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.indices.contains_key(name)
+    }
+
+    /// Names of the nodes `name` has an edge to. Unknown names have no neighbours.
+    pub fn neighbors(&self, name: &str) -> impl Iterator<Item = &'a str> + '_ {
+        self.index(name)
+            .into_iter()
+            .flat_map(|index| self.graph.neighbors(index))
+            .map(|index| self.graph[index])
+    }
+
+    /// Names of the nodes with an edge to `name`
+    pub fn predecessors(&self, name: &str) -> impl Iterator<Item = &'a str> + '_ {
+        self.index(name)
+            .into_iter()
+            .flat_map(|index| self.graph.neighbors_directed(index, Direction::Incoming))
+            .map(|index| self.graph[index])
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.graph.node_weights().copied()
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    /// Count the paths from `from` to `to`. Unknown names have no paths.
+    pub fn count_paths(&self, from: &str, to: &str) -> Result<u64, PathError<&'a str>> {
+        self.count_paths_via(from, to, &[])
+    }
+
+    /// Count the paths from `from` to `to` which visit every one of `waypoints`, in any order
+    pub fn count_paths_via(
+        &self,
+        from: &str,
+        to: &str,
+        waypoints: &[&str],
+    ) -> Result<u64, PathError<&'a str>> {
+        let (Some(from), Some(to)) = (self.index(from), self.index(to)) else {
+            return Ok(0);
+        };
+        let Some(waypoints) = waypoints
+            .iter()
+            .map(|name| self.index(name))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Ok(0);
+        };
+        count_paths_via(&self.graph, from, to, &waypoints).map_err(|e| match e {
+            PathError::Cycle { node } => PathError::Cycle {
+                node: self.name(node),
+            },
+            PathError::Overflow => PathError::Overflow,
+            PathError::TooManyWaypoints { count } => PathError::TooManyWaypoints { count },
+        })
+    }
+
+    /// The underlying graph, for running petgraph algorithms directly
+    pub fn graph(&self) -> &DiGraph<&'a str, ()> {
+        &self.graph
+    }
+}
+
+/// The most waypoints a path count can require, since each needs a bit of the mask
+pub const MAX_WAYPOINTS: usize = 31;
+
+/// Why paths couldn't be counted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathError<N> {
+    /// The graph has a cycle passing through `node`, so there may be infinitely many
+    Cycle { node: N },
+    /// There are more paths than fit in a `u64`
+    Overflow,
+    /// More waypoints than `MAX_WAYPOINTS`
+    TooManyWaypoints { count: usize },
+}
+
+impl<N: Debug> Display for PathError<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::Cycle { node } => write!(f, "graph has a cycle through node {:?}", node),
+            PathError::Overflow => write!(f, "too many paths to count in a u64"),
+            PathError::TooManyWaypoints { count } => write!(
+                f,
+                "can't track {} waypoints, the most is {}",
+                count, MAX_WAYPOINTS
+            ),
+        }
+    }
+}
+
+impl<N: Debug> Error for PathError<N> {}
+
+/// Count the paths from `start` to `end` in a directed acyclic graph
+pub fn count_paths<N, E>(
+    graph: &DiGraph<N, E>,
+    start: NodeIndex,
+    end: NodeIndex,
+) -> Result<u64, PathError<NodeIndex>> {
+    count_paths_via(graph, start, end, &[])
+}
+
+/// Count the paths from `start` to `end` which visit every node in `waypoints`, in any order.
+///
+/// Nodes are visited in topological order, carrying a count per subset of waypoints seen
+/// so far, so this is O((V + E) * 2^waypoints) rather than exponential in the path length.
+/// Only the part of the graph reachable from `start` needs to be acyclic.
+pub fn count_paths_via<N, E>(
+    graph: &DiGraph<N, E>,
+    start: NodeIndex,
+    end: NodeIndex,
+    waypoints: &[NodeIndex],
+) -> Result<u64, PathError<NodeIndex>> {
+    if waypoints.len() > MAX_WAYPOINTS {
+        return Err(PathError::TooManyWaypoints {
+            count: waypoints.len(),
+        });
+    }
+    let waypoint_bit = |node: NodeIndex| {
+        waypoints
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| **w == node)
+            .fold(0, |mask, (i, _)| mask | (1 << i))
+    };
+    let subsets = 1 << waypoints.len();
+    let mut counts: Vec<Vec<u64>> = vec![vec![]; graph.node_count()];
+    counts[start.index()] = vec![0; subsets];
+    counts[start.index()][waypoint_bit(start)] = 1;
+    for node in reachable_topological_order(graph, start)? {
+        if node == end {
+            // A path can't come back to the end once it leaves, so don't extend any further
+            break;
+        }
+        // Every path into this node has been counted, so this is the last time it's needed
+        let current = std::mem::take(&mut counts[node.index()]);
+        for next in graph.neighbors(node) {
+            let bit = waypoint_bit(next);
+            let next_counts = &mut counts[next.index()];
+            if next_counts.is_empty() {
+                next_counts.resize(subsets, 0);
+            }
+            for (mask, count) in current.iter().enumerate() {
+                let total = &mut next_counts[mask | bit];
+                *total = total.checked_add(*count).ok_or(PathError::Overflow)?;
+            }
+        }
+    }
+    Ok(counts[end.index()].get(subsets - 1).copied().unwrap_or(0))
+}
+
+// Reverse post-order of a depth first search from start, failing on any back edge
+fn reachable_topological_order<N, E>(
+    graph: &DiGraph<N, E>,
+    start: NodeIndex,
+) -> Result<Vec<NodeIndex>, PathError<NodeIndex>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Visit {
+        Unseen,
+        InProgress,
+        Done,
+    }
+    let mut visits = vec![Visit::Unseen; graph.node_count()];
+    let mut order = vec![];
+    let mut stack: Vec<(NodeIndex, Neighbors<E>)> = vec![(start, graph.neighbors(start))];
+    visits[start.index()] = Visit::InProgress;
+    while let Some((node, neighbors)) = stack.last_mut() {
+        match neighbors.next() {
+            Some(next) => match visits[next.index()] {
+                Visit::Unseen => {
+                    visits[next.index()] = Visit::InProgress;
+                    stack.push((next, graph.neighbors(next)));
+                }
+                Visit::InProgress => return Err(PathError::Cycle { node: next }),
+                Visit::Done => {}
+            },
+            None => {
+                visits[node.index()] = Visit::Done;
+                order.push(*node);
+                stack.pop();
+            }
+        }
+    }
+    order.reverse();
+    Ok(order)
+}
+
+fn parse_adjacency(input: &str) -> IResult<&str, (&str, Vec<&str>)> {
+    separated_pair(parse_name, tag(":"), many0(preceded(space1, parse_name)))(input)
+}
+
+fn parse_name(input: &str) -> IResult<&str, &str> {
+    is_not(": \t")(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    #[test]
+    fn test_from_adjacency_list() -> color_eyre::Result<()> {
+        let graph = NamedGraph::from_adjacency_list("you: bbb ccc\nbbb: out\nccc: bbb out\n")?;
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.edge_count(), 5);
+        assert_eq!(
+            graph.neighbors("ccc").sorted().collect_vec(),
+            vec!["bbb", "out"]
+        );
+        assert_eq!(
+            graph.predecessors("bbb").sorted().collect_vec(),
+            vec!["ccc", "you"]
+        );
+        assert_eq!(graph.neighbors("out").count(), 0);
+        assert_eq!(graph.neighbors("missing").count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_and_name() -> color_eyre::Result<()> {
+        let mut graph = NamedGraph::new();
+        let a = graph.add_node("a");
+        graph.add_edge("a", "b");
+        assert_eq!(graph.add_node("a"), a);
+        assert_eq!(graph.index("a"), Some(a));
+        assert_eq!(graph.name(graph.index("b").unwrap()), "b");
+        assert!(!graph.contains("c"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_paths() -> color_eyre::Result<()> {
+        let graph = NamedGraph::from_adjacency_list(include_str!("../input/day11.test.txt"))?;
+        assert_eq!(graph.count_paths("you", "out")?, 5);
+        assert_eq!(graph.count_paths("you", "missing")?, 0);
+        assert_eq!(graph.count_paths("out", "you")?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_paths_via() -> color_eyre::Result<()> {
+        let graph = NamedGraph::from_adjacency_list(include_str!("../input/day11.test.2.txt"))?;
+        assert_eq!(graph.count_paths("svr", "out")?, 8);
+        assert_eq!(graph.count_paths_via("svr", "out", &["fft", "dac"])?, 2);
+        assert_eq!(graph.count_paths_via("svr", "out", &["fft"])?, 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_paths_cycle() -> color_eyre::Result<()> {
+        let graph = NamedGraph::from_adjacency_list("a: b\nb: c\nc: a out\nx: x")?;
+        assert_eq!(
+            graph.count_paths("a", "out"),
+            Err(PathError::Cycle { node: "a" })
+        );
+        // The cycle on x isn't reachable, so doesn't matter
+        let graph = NamedGraph::from_adjacency_list("a: out\nx: x")?;
+        assert_eq!(graph.count_paths("a", "out"), Ok(1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_paths_limits() -> color_eyre::Result<()> {
+        // Each diamond doubles the paths, so 64 of them in a row is one too many
+        let text = (0..64)
+            .map(|i| format!("n{i}: a{i} b{i}\na{i}: n{}\nb{i}: n{}\n", i + 1, i + 1))
+            .collect::<String>();
+        let graph =
+            NamedGraph::from_adjacency_list(&text).map_err(|e| color_eyre::eyre::eyre!("{}", e))?;
+        assert_eq!(graph.count_paths("n0", "n63"), Ok(1 << 63));
+        assert_eq!(graph.count_paths("n0", "n64"), Err(PathError::Overflow));
+        let waypoints = ["n1"; MAX_WAYPOINTS + 1];
+        assert_eq!(
+            graph.count_paths_via("n0", "n2", &waypoints),
+            Err(PathError::TooManyWaypoints { count: 32 })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_adjacency_list_error() -> color_eyre::Result<()> {
+        assert!(NamedGraph::from_adjacency_list("you bbb").is_err());
+        Ok(())
+    }
+}
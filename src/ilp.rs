@@ -0,0 +1,339 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use num::{Signed, Zero};
+
+use crate::linalg::{Matrix, Overflow, Rational, add, mul, sub};
+
+/// Minimise `objective · x` subject to `Ax = b` and `x ≥ 0`, with every `x` an integer.
+///
+/// The equations are row reduced over the rationals, which leaves each pivot variable as
+/// a function of the free variables, and then the free variables are searched by branch
+/// and bound. That's fast when there are only a few more variables than independent
+/// equations, as in puzzles where some buttons have to be pressed to hit exact counts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IntegerProgram {
+    constraints: Vec<Vec<i64>>,
+    targets: Vec<i64>,
+    objective: Vec<i64>,
+    upper_bounds: Vec<Option<i64>>,
+}
+
+/// An optimal assignment, and the value of the objective there
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Assignment {
+    pub values: Vec<i64>,
+    pub objective: i64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IlpError {
+    /// A free variable has no upper bound, so it can't be searched exhaustively
+    Unbounded { variable: usize },
+    /// Row reduction or the search needed fractions too big for an `i128`, or an answer
+    /// too big for an `i64`
+    Overflow,
+}
+
+impl Display for IlpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IlpError::Unbounded { variable } => {
+                write!(f, "variable {} has no upper bound", variable)
+            }
+            IlpError::Overflow => write!(f, "{}", Overflow),
+        }
+    }
+}
+
+impl Error for IlpError {}
+
+impl From<Overflow> for IlpError {
+    fn from(_: Overflow) -> Self {
+        IlpError::Overflow
+    }
+}
+
+impl IntegerProgram {
+    /// `constraints` has one row per equation and one column per variable.
+    ///
+    /// Upper bounds are worked out from any equation whose coefficients are all
+    /// non-negative: then no variable in it can exceed the target over its coefficient.
+    pub fn new(
+        constraints: Vec<Vec<i64>>,
+        targets: Vec<i64>,
+        objective: Vec<i64>,
+    ) -> IntegerProgram {
+        let variables = objective.len();
+        assert_eq!(
+            constraints.len(),
+            targets.len(),
+            "need one target per constraint"
+        );
+        assert!(
+            constraints.iter().all(|row| row.len() == variables),
+            "need one coefficient per variable in each constraint"
+        );
+        let mut upper_bounds = vec![None; variables];
+        for (row, target) in constraints.iter().zip(&targets) {
+            if *target < 0 || row.iter().any(|a| *a < 0) {
+                continue;
+            }
+            for (bound, a) in upper_bounds.iter_mut().zip(row) {
+                if *a > 0 {
+                    let limit = target / a;
+                    *bound = Some(bound.map_or(limit, |b: i64| b.min(limit)));
+                }
+            }
+        }
+        IntegerProgram {
+            constraints,
+            targets,
+            objective,
+            upper_bounds,
+        }
+    }
+
+    /// Limit `variable` to at most `bound`, on top of any bound already known
+    pub fn with_upper_bound(mut self, variable: usize, bound: i64) -> IntegerProgram {
+        let current = &mut self.upper_bounds[variable];
+        *current = Some(current.map_or(bound, |b| b.min(bound)));
+        self
+    }
+
+    /// The assignment with the lowest objective, or `None` if there's no solution
+    pub fn minimise(&self) -> Result<Option<Assignment>, IlpError> {
+        let Some(reduced) = Reduced::new(&self.constraints, &self.targets)? else {
+            return Ok(None);
+        };
+        let mut free_bounds = vec![];
+        for free in &reduced.free {
+            // A variable in no constraint only adds to the objective, so if it doesn't
+            // lower it the best choice is zero
+            let unconstrained = self.constraints.iter().all(|row| row[*free] == 0);
+            match self.upper_bounds[*free] {
+                Some(bound) => free_bounds.push(bound),
+                None if unconstrained && self.objective[*free] >= 0 => free_bounds.push(0),
+                None => return Err(IlpError::Unbounded { variable: *free }),
+            }
+        }
+        // Substitute the pivots into the objective, leaving it in terms of the free variables
+        let mut constant = Rational::zero();
+        let mut free_costs = reduced
+            .free
+            .iter()
+            .map(|f| Rational::from(self.objective[*f] as i128))
+            .collect::<Vec<_>>();
+        for (row, pivot) in reduced.pivots.iter().enumerate() {
+            let cost = Rational::from(self.objective[*pivot] as i128);
+            constant = add(&constant, &mul(&cost, &reduced.rhs[row])?)?;
+            for (k, coefficient) in reduced.free_coefficients[row].iter().enumerate() {
+                free_costs[k] = sub(&free_costs[k], &mul(&cost, coefficient)?)?;
+            }
+        }
+        let mut search = Search {
+            program: self,
+            reduced: &reduced,
+            free_bounds,
+            free_costs,
+            values: vec![0; reduced.free.len()],
+            best: None,
+        };
+        search.branch(0, reduced.rhs.clone(), constant)?;
+        Ok(search.best)
+    }
+}
+
+// The constraints in reduced row echelon form. Each pivot variable is
+// `rhs[row] - sum(free_coefficients[row][k] * x[free[k]])`.
+struct Reduced {
+    pivots: Vec<usize>,
+    free: Vec<usize>,
+    rhs: Vec<Rational>,
+    free_coefficients: Vec<Vec<Rational>>,
+}
+
+impl Reduced {
+    // None if the equations are inconsistent
+    fn new(constraints: &[Vec<i64>], targets: &[i64]) -> Result<Option<Reduced>, Overflow> {
+        let targets = targets
+            .iter()
+            .map(|t| Rational::from(*t as i128))
+            .collect::<Vec<_>>();
+        let Some(solution) = Matrix::from_integers(constraints).solve(&targets)? else {
+            return Ok(None);
+        };
+        Ok(Some(Reduced {
+            rhs: solution
+                .pivots
+                .iter()
+                .map(|p| solution.particular[*p])
+                .collect(),
+            free_coefficients: solution
+                .pivots
+                .iter()
+                .map(|p| {
+                    solution
+                        .null_space
+                        .iter()
+                        .map(|n| sub(&Rational::zero(), &n[*p]))
+                        .collect()
+                })
+                .collect::<Result<_, _>>()?,
+            pivots: solution.pivots,
+            free: solution.free,
+        }))
+    }
+}
+
+struct Search<'a> {
+    program: &'a IntegerProgram,
+    reduced: &'a Reduced,
+    free_bounds: Vec<i64>,
+    free_costs: Vec<Rational>,
+    values: Vec<i64>,
+    best: Option<Assignment>,
+}
+
+impl Search<'_> {
+    // Choose a value for free variable `depth`, given the pivots' remaining values
+    // with the free variables before it substituted in, and the objective so far
+    fn branch(
+        &mut self,
+        depth: usize,
+        remaining: Vec<Rational>,
+        objective: Rational,
+    ) -> Result<(), Overflow> {
+        let unassigned = depth..self.free_bounds.len();
+        // The cheapest the objective could get, letting each unassigned variable go to
+        // whichever end of its range is cheaper
+        let mut lowest = objective;
+        for k in unassigned.clone() {
+            let extreme = mul(&self.free_costs[k], &self.bound(k))?;
+            lowest = add(&lowest, &extreme.min(Rational::zero()))?;
+        }
+        if self
+            .best
+            .as_ref()
+            .is_some_and(|best| lowest >= Rational::from(best.objective as i128))
+        {
+            return Ok(());
+        }
+        // Give up if some pivot has to go negative however the rest are chosen
+        for (row, value) in remaining.iter().enumerate() {
+            let mut highest = *value;
+            for k in unassigned.clone() {
+                let extreme = mul(&self.reduced.free_coefficients[row][k], &self.bound(k))?;
+                highest = sub(&highest, &extreme.min(Rational::zero()))?;
+            }
+            if highest.is_negative() {
+                return Ok(());
+            }
+        }
+        if depth == self.free_bounds.len() {
+            return self.record(&remaining, objective);
+        }
+        for value in 0..=self.free_bounds[depth] {
+            self.values[depth] = value;
+            let x = Rational::from(value as i128);
+            let next = remaining
+                .iter()
+                .enumerate()
+                .map(|(row, r)| sub(r, &mul(&self.reduced.free_coefficients[row][depth], &x)?))
+                .collect::<Result<_, _>>()?;
+            let cost = mul(&self.free_costs[depth], &x)?;
+            self.branch(depth + 1, next, add(&objective, &cost)?)?;
+        }
+        Ok(())
+    }
+
+    fn bound(&self, k: usize) -> Rational {
+        Rational::from(self.free_bounds[k] as i128)
+    }
+
+    fn record(&mut self, pivot_values: &[Rational], objective: Rational) -> Result<(), Overflow> {
+        let mut values = vec![0; self.program.objective.len()];
+        for (k, free) in self.reduced.free.iter().enumerate() {
+            values[*free] = self.values[k];
+        }
+        for (value, pivot) in pivot_values.iter().zip(&self.reduced.pivots) {
+            if !value.is_integer() {
+                return Ok(());
+            }
+            let value = i64::try_from(value.to_integer()).map_err(|_| Overflow)?;
+            if self.program.upper_bounds[*pivot].is_some_and(|bound| value > bound) {
+                return Ok(());
+            }
+            values[*pivot] = value;
+        }
+        self.best = Some(Assignment {
+            values,
+            objective: i64::try_from(objective.to_integer()).map_err(|_| Overflow)?,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimise_coins() -> color_eyre::Result<()> {
+        // Making 37 from coins of 1, 5 and 10 takes six coins at fewest
+        let program = IntegerProgram::new(vec![vec![1, 5, 10]], vec![37], vec![1; 3]);
+        let best = program.minimise()?.unwrap();
+        assert_eq!(best.values, vec![2, 1, 3]);
+        assert_eq!(best.objective, 6);
+        // If tens cost as much as four coins, fives win
+        let program = IntegerProgram::new(vec![vec![1, 5, 10]], vec![37], vec![1, 1, 4]);
+        assert_eq!(program.minimise()?.unwrap().values, vec![2, 7, 0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_minimise_needs_integers() -> color_eyre::Result<()> {
+        // 2x + 2y = 3 has rational solutions but no integer ones
+        let program = IntegerProgram::new(vec![vec![2, 2]], vec![3], vec![1, 1]);
+        assert_eq!(program.minimise()?, None);
+        // x + y = 4, x - y = 2 has the single solution x = 3, y = 1
+        let program = IntegerProgram::new(vec![vec![1, 1], vec![1, -1]], vec![4, 2], vec![1, 5]);
+        assert_eq!(
+            program.minimise()?,
+            Some(Assignment {
+                values: vec![3, 1],
+                objective: 8
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_minimise_unbounded() -> color_eyre::Result<()> {
+        // x - y = 1 leaves y free with nothing to stop it growing
+        let program = IntegerProgram::new(vec![vec![1, -1]], vec![1], vec![1, 1]);
+        assert_eq!(program.minimise(), Err(IlpError::Unbounded { variable: 1 }));
+        let best = program.with_upper_bound(1, 10).minimise()?.unwrap();
+        assert_eq!(best.values, vec![1, 0]);
+        // A variable in no constraint is left at zero unless it pays to increase it
+        let program = IntegerProgram::new(vec![vec![1, 0]], vec![3], vec![1, 1]);
+        assert_eq!(program.minimise()?.unwrap().values, vec![3, 0]);
+        let program = IntegerProgram::new(vec![vec![1, 0]], vec![3], vec![1, -1]);
+        assert_eq!(program.minimise(), Err(IlpError::Unbounded { variable: 1 }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_minimise_overflow() -> color_eyre::Result<()> {
+        // Each variable is forced to i64::MAX, so the objective is about 3 * 2^126
+        let constraints = (0..3)
+            .map(|i| (0..3).map(|j| (i == j) as i64).collect())
+            .collect();
+        let program = IntegerProgram::new(constraints, vec![i64::MAX; 3], vec![i64::MAX; 3]);
+        assert_eq!(program.minimise(), Err(IlpError::Overflow));
+        // An objective that fits in an i128 but not an i64
+        let program = IntegerProgram::new(vec![vec![1]], vec![i64::MAX], vec![2]);
+        assert_eq!(program.minimise(), Err(IlpError::Overflow));
+        Ok(())
+    }
+}
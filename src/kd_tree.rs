@@ -0,0 +1,353 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::metric::squared_euclidean;
+
+/// A k-d tree over integer points in `K` dimensions, e.g. `KdTree<3>` for `[x, y, z]`.
+///
+/// Points are identified by their index in the slice the tree was built from.
+/// All distances are squared Euclidean distances, so they're exact and ties are
+/// broken by index, which keeps every query deterministic. Those distances have to fit in
+/// a `u128`, as described on `squared_euclidean`, so the tree and its queries give `None`
+/// for points too far apart to measure.
+#[derive(Clone, Debug)]
+pub struct KdTree<const K: usize> {
+    // An implicit balanced tree: the node for a range is at its midpoint, split on depth % K
+    nodes: Vec<(usize, [i64; K])>,
+    // The lowest and highest coordinate on each axis
+    bounds: Option<([i64; K], [i64; K])>,
+}
+
+/// A point found by a query, and its squared distance from the query point
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Neighbour {
+    pub distance: u128,
+    pub index: usize,
+}
+
+impl<const K: usize> KdTree<K> {
+    /// `None` if the points span too far for the distances between them to fit. `K` has
+    /// to be at least one, since each level of the tree splits on an axis.
+    pub fn new(points: &[[i64; K]]) -> Option<KdTree<K>> {
+        const { assert!(K > 0, "a k-d tree needs at least one dimension") };
+        let mut nodes = points.iter().copied().enumerate().collect::<Vec<_>>();
+        build(&mut nodes, 0);
+        let tree = KdTree {
+            bounds: bounding_box(points),
+            nodes,
+        };
+        if let Some((lower, upper)) = tree.bounds {
+            squared_euclidean(&lower, &upper)?;
+        }
+        Some(tree)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The point closest to `target`, or `None` if the tree is empty or `target` is too
+    /// far away to measure
+    pub fn nearest(&self, target: &[i64; K]) -> Option<Neighbour> {
+        self.k_nearest(target, 1)?.into_iter().next()
+    }
+
+    /// The `k` points closest to `target`, closest first, or `None` if `target` is too far
+    /// away to measure
+    pub fn k_nearest(&self, target: &[i64; K], k: usize) -> Option<Vec<Neighbour>> {
+        self.measurable(target)
+            .then(|| self.k_nearest_excluding(target, k, None))
+    }
+
+    /// Every point within `max_distance` (squared, inclusive) of `target`, closest first,
+    /// or `None` if `target` is too far away to measure
+    pub fn within(&self, target: &[i64; K], max_distance: u128) -> Option<Vec<Neighbour>> {
+        if !self.measurable(target) {
+            return None;
+        }
+        let mut found = vec![];
+        self.search_within(0, self.nodes.len(), 0, target, max_distance, &mut found);
+        found.sort_unstable();
+        Some(found)
+    }
+
+    /// Every pair of distinct points `(a, b, distance)` with `a < b`, closest first, ties
+    /// ordered by `(a, b)`.
+    ///
+    /// Pairs are produced lazily by merging each point's nearest neighbours, so taking
+    /// the first few pairs doesn't need all O(n²) of them in memory.
+    pub fn closest_pairs(&self) -> ClosestPairs<'_, K> {
+        let mut points = vec![[0; K]; self.nodes.len()];
+        for (index, point) in &self.nodes {
+            points[*index] = *point;
+        }
+        let mut pairs = ClosestPairs {
+            tree: self,
+            points,
+            neighbours: vec![vec![]; self.nodes.len()],
+            next: vec![0; self.nodes.len()],
+            heap: BinaryHeap::new(),
+        };
+        for index in 0..self.nodes.len() {
+            pairs.advance(index);
+        }
+        pairs
+    }
+
+    // Whether every point's distance from `target` fits, which it does if the bounding box
+    // stretched to cover `target` has a diagonal that fits
+    fn measurable(&self, target: &[i64; K]) -> bool {
+        let Some((mut lower, mut upper)) = self.bounds else {
+            return true;
+        };
+        for axis in 0..K {
+            lower[axis] = lower[axis].min(target[axis]);
+            upper[axis] = upper[axis].max(target[axis]);
+        }
+        squared_euclidean(&lower, &upper).is_some()
+    }
+
+    fn k_nearest_excluding(
+        &self,
+        target: &[i64; K],
+        k: usize,
+        exclude: Option<usize>,
+    ) -> Vec<Neighbour> {
+        if k == 0 {
+            return vec![];
+        }
+        let mut best = BinaryHeap::with_capacity(k + 1);
+        self.search_nearest(0, self.nodes.len(), 0, target, k, exclude, &mut best);
+        best.into_sorted_vec()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn search_nearest(
+        &self,
+        start: usize,
+        end: usize,
+        depth: usize,
+        target: &[i64; K],
+        k: usize,
+        exclude: Option<usize>,
+        best: &mut BinaryHeap<Neighbour>,
+    ) {
+        if start >= end {
+            return;
+        }
+        let mid = start + (end - start) / 2;
+        let (index, point) = self.nodes[mid];
+        if Some(index) != exclude {
+            best.push(Neighbour {
+                distance: distance(&point, target),
+                index,
+            });
+            if best.len() > k {
+                best.pop();
+            }
+        }
+        let axis = depth % K;
+        let offset = target[axis] as i128 - point[axis] as i128;
+        let (near, far) = if offset < 0 {
+            ((start, mid), (mid + 1, end))
+        } else {
+            ((mid + 1, end), (start, mid))
+        };
+        self.search_nearest(near.0, near.1, depth + 1, target, k, exclude, best);
+        // An equally distant point could still have a lower index, so only prune when
+        // the splitting plane is strictly further away than the worst point kept
+        let plane = offset.unsigned_abs().pow(2);
+        if best.len() < k || best.peek().is_some_and(|worst| plane <= worst.distance) {
+            self.search_nearest(far.0, far.1, depth + 1, target, k, exclude, best);
+        }
+    }
+
+    fn search_within(
+        &self,
+        start: usize,
+        end: usize,
+        depth: usize,
+        target: &[i64; K],
+        max_distance: u128,
+        found: &mut Vec<Neighbour>,
+    ) {
+        if start >= end {
+            return;
+        }
+        let mid = start + (end - start) / 2;
+        let (index, point) = self.nodes[mid];
+        let distance = distance(&point, target);
+        if distance <= max_distance {
+            found.push(Neighbour { distance, index });
+        }
+        let axis = depth % K;
+        let offset = target[axis] as i128 - point[axis] as i128;
+        let plane = offset.unsigned_abs().pow(2);
+        if offset < 0 || plane <= max_distance {
+            self.search_within(start, mid, depth + 1, target, max_distance, found);
+        }
+        if offset >= 0 || plane <= max_distance {
+            self.search_within(mid + 1, end, depth + 1, target, max_distance, found);
+        }
+    }
+}
+
+/// Lazy iterator over point pairs in increasing distance, from `KdTree::closest_pairs`
+pub struct ClosestPairs<'a, const K: usize> {
+    tree: &'a KdTree<K>,
+    points: Vec<[i64; K]>,
+    // A prefix of each point's neighbours, closest first, fetched in doubling batches
+    neighbours: Vec<Vec<Neighbour>>,
+    next: Vec<usize>,
+    heap: BinaryHeap<Reverse<(u128, usize, usize, usize)>>,
+}
+
+impl<const K: usize> ClosestPairs<'_, K> {
+    // Queue up the next closest neighbour of `index`, if it has any left
+    fn advance(&mut self, index: usize) {
+        let position = self.next[index];
+        if position == self.neighbours[index].len() && position + 1 < self.points.len() {
+            let batch = (2 * position).max(4);
+            self.neighbours[index] =
+                self.tree
+                    .k_nearest_excluding(&self.points[index], batch, Some(index));
+        }
+        if let Some(neighbour) = self.neighbours[index].get(position) {
+            let (a, b) = if index < neighbour.index {
+                (index, neighbour.index)
+            } else {
+                (neighbour.index, index)
+            };
+            self.heap.push(Reverse((neighbour.distance, a, b, index)));
+            self.next[index] += 1;
+        }
+    }
+}
+
+impl<const K: usize> Iterator for ClosestPairs<'_, K> {
+    type Item = (usize, usize, u128);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Each pair is seen from both ends, so only report it from the lower index
+        while let Some(Reverse((distance, a, b, owner))) = self.heap.pop() {
+            self.advance(owner);
+            if owner == a {
+                return Some((a, b, distance));
+            }
+        }
+        None
+    }
+}
+
+fn bounding_box<const K: usize>(points: &[[i64; K]]) -> Option<([i64; K], [i64; K])> {
+    let (first, rest) = points.split_first()?;
+    let (mut lower, mut upper) = (*first, *first);
+    for point in rest {
+        for axis in 0..K {
+            lower[axis] = lower[axis].min(point[axis]);
+            upper[axis] = upper[axis].max(point[axis]);
+        }
+    }
+    Some((lower, upper))
+}
+
+// Only called on points already known to be within the bounding box diagonal of each other
+fn distance<const K: usize>(a: &[i64; K], b: &[i64; K]) -> u128 {
+    squared_euclidean(a, b).expect("distance checked against the bounding box")
+}
+
+fn build<const K: usize>(nodes: &mut [(usize, [i64; K])], depth: usize) {
+    if nodes.len() <= 1 {
+        return;
+    }
+    let axis = depth % K;
+    let mid = nodes.len() / 2;
+    nodes.select_nth_unstable_by_key(mid, |(_, point)| point[axis]);
+    let (left, right) = nodes.split_at_mut(mid);
+    build(left, depth + 1);
+    build(&mut right[1..], depth + 1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    fn points() -> Vec<[i64; 2]> {
+        vec![[0, 0], [5, 5], [1, 1], [9, 0], [-3, 4], [5, 6], [0, 0]]
+    }
+
+    #[test]
+    fn test_nearest() -> color_eyre::Result<()> {
+        let tree = KdTree::new(&points()).unwrap();
+        assert_eq!(
+            tree.nearest(&[4, 4]),
+            Some(Neighbour {
+                distance: 2,
+                index: 1
+            })
+        );
+        assert_eq!(
+            tree.k_nearest(&[1, 0], 3)
+                .unwrap()
+                .iter()
+                .map(|n| n.index)
+                .collect_vec(),
+            vec![0, 2, 6]
+        );
+        assert_eq!(
+            tree.within(&[0, 0], 25)
+                .unwrap()
+                .iter()
+                .map(|n| n.index)
+                .collect_vec(),
+            vec![0, 6, 2, 4]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_closest_pairs_match_brute_force() -> color_eyre::Result<()> {
+        let points = (0..40)
+            .map(|i: i64| [(i * 37) % 23 - 11, (i * 53) % 19 - 9, (i * 11) % 7])
+            .collect_vec();
+        let tree = KdTree::new(&points).unwrap();
+        let expected = (0..points.len())
+            .tuple_combinations()
+            .map(|(a, b)| (a, b, distance(&points[a], &points[b])))
+            .sorted_by_key(|(a, b, d)| (*d, *a, *b))
+            .collect_vec();
+        assert_eq!(tree.closest_pairs().collect_vec(), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extreme_coordinates() -> color_eyre::Result<()> {
+        // A splitting plane 2^64 away only fits once squared as a u128
+        let tree = KdTree::new(&[[i64::MIN], [i64::MAX], [0]]).unwrap();
+        assert_eq!(tree.nearest(&[i64::MAX - 1]).map(|n| n.index), Some(1));
+        assert_eq!(
+            tree.within(&[i64::MIN], 0).map(|found| found.len()),
+            Some(1)
+        );
+        assert_eq!(tree.closest_pairs().count(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unmeasurable_distances() -> color_eyre::Result<()> {
+        assert!(KdTree::new(&[[i64::MIN, i64::MIN], [i64::MAX, i64::MAX]]).is_none());
+        let tree = KdTree::new(&[[0, 0], [i64::MAX, i64::MAX]]).unwrap();
+        assert_eq!(tree.nearest(&[i64::MIN, i64::MIN]), None);
+        assert_eq!(tree.k_nearest(&[i64::MIN, 0], 2), None);
+        assert_eq!(tree.within(&[i64::MIN, i64::MIN], 0), None);
+        assert_eq!(tree.nearest(&[-1, -1]).map(|n| n.index), Some(0));
+        let empty = KdTree::<2>::new(&[]).unwrap();
+        assert_eq!(empty.nearest(&[i64::MIN, i64::MIN]), None);
+        Ok(())
+    }
+}
@@ -1,9 +1,28 @@
+pub mod automaton;
+pub mod bitset;
+pub mod compression;
 pub mod coordinate;
+pub mod cycle;
+pub mod digits;
+pub mod geometry;
+pub mod gf2;
+pub mod graph;
+pub mod ilp;
+pub mod kd_tree;
+pub mod linalg;
+pub mod metric;
+pub mod modular;
+pub mod number_theory;
 pub mod parsing;
+pub mod prefix_sum;
 pub mod range;
+pub mod search;
+pub mod spanning_tree;
+pub mod union_find;
 // Force template to compile
 mod template;
-use std::fmt::Display;
+use std::error::Error;
+use std::fmt::{self, Display};
 use std::fs::File;
 use std::io::BufReader;
 use std::io::{BufRead, Lines};
@@ -64,19 +83,72 @@ pub fn parse_lines_to_nums(lines: Lines<BufReader<File>>) -> Vec<i32> {
         .collect_vec()
 }
 
-// #[allow(dead_code)]
-// fn parse_dates(reader: BufReader<File>) -> Vec<Date> {
-//     parse_lines_to_struct::<Date>(reader)
-// }
+/// A failure to parse one line of an input, with the 1-based number of the line it came from
+#[derive(Debug)]
+pub struct LineError {
+    pub line_number: usize,
+    pub line: String,
+    pub cause: Box<dyn Error + Send + Sync>,
+}
 
-// // Create a method for parsing lines of a file to a particular struct using reformation
-// #[allow(dead_code)]
-// pub fn parse_lines_to_struct<'a, T: Reformation<'a>>(reader: BufReader<File>) -> Vec<T> {
-//     reader
-//         .lines()
-//         .map(|line| T::parse(&line.unwrap()).unwrap())
-//         .collect_vec()
-// }
+impl Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {} ({:?}): {}",
+            self.line_number, self.line, self.cause
+        )
+    }
+}
+
+impl Error for LineError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.cause.as_ref())
+    }
+}
+
+/// Parse each line of `input` into `T` using reformation.
+///
+/// Each line borrows from `input` rather than a temporary, so `T` may hold `&'a str` fields.
+pub fn parse_lines_to_struct<'a, T: Reformation<'a>>(input: &'a str) -> Result<Vec<T>, LineError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            T::parse(line).map_err(|cause| LineError {
+                line_number: index + 1,
+                line: line.to_string(),
+                cause: Box::new(cause),
+            })
+        })
+        .collect()
+}
+
+/// Parse each line of a file into an owned `T` using reformation.
+///
+/// The lines don't outlive this call, so `T` can't borrow from them; use
+/// `parse_lines_to_struct` on the whole input for borrowing types.
+pub fn parse_reader_to_struct<T>(reader: BufReader<File>) -> Result<Vec<T>, LineError>
+where
+    T: for<'a> Reformation<'a>,
+{
+    reader
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let line = line.map_err(|cause| LineError {
+                line_number: index + 1,
+                line: String::new(),
+                cause: Box::new(cause),
+            })?;
+            T::parse(&line).map_err(|cause| LineError {
+                line_number: index + 1,
+                line: line.clone(),
+                cause: Box::new(cause),
+            })
+        })
+        .collect()
+}
 
 pub fn transpose<T: Copy>(matrix: &[Vec<T>]) -> Vec<Vec<T>> {
     matrix
@@ -97,3 +169,56 @@ pub fn print_matrix<T: Display>(matrix: &[Vec<T>]) {
     }
     println!();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Reformation, Debug, PartialEq)]
+    #[reformation(r"{name}: {size}")]
+    struct Named<'a> {
+        name: &'a str,
+        size: u32,
+    }
+
+    #[test]
+    fn test_parse_lines_to_struct_owned() -> color_eyre::Result<()> {
+        let dates = parse_lines_to_struct::<Date>("2025-12-01 09:30\n2025-12-25 00:00")?;
+        assert_eq!(dates.len(), 2);
+        assert_eq!(dates[1].day, 25);
+        let ants = parse_lines_to_struct::<Ant>("Queen(Bess)\nWorker(3)\nWarrior")?;
+        assert_eq!(
+            ants,
+            vec![Ant::Queen("Bess".to_string()), Ant::Worker(3), Ant::Warrior]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_lines_to_struct_borrowed() -> color_eyre::Result<()> {
+        let input = "aaa: 3\nbbb: 14".to_string();
+        let named = parse_lines_to_struct::<Named>(&input)?;
+        assert_eq!(
+            named,
+            vec![
+                Named {
+                    name: "aaa",
+                    size: 3
+                },
+                Named {
+                    name: "bbb",
+                    size: 14
+                }
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_lines_to_struct_error() -> color_eyre::Result<()> {
+        let error = parse_lines_to_struct::<Date>("2025-12-01 09:30\nnot a date").unwrap_err();
+        assert_eq!(error.line_number, 2);
+        assert_eq!(error.line, "not a date");
+        Ok(())
+    }
+}
@@ -0,0 +1,335 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use num::rational::Ratio;
+use num::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, One, Zero};
+
+/// An exact fraction. Every operation in this module is checked, so results are never
+/// silently wrong.
+pub type Rational = Ratio<i128>;
+
+/// An intermediate value didn't fit in an `i128` numerator or denominator
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Overflow;
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rational arithmetic overflowed")
+    }
+}
+
+impl Error for Overflow {}
+
+// Checked arithmetic on fractions, shared with other exact solvers in the crate
+pub(crate) fn add(a: &Rational, b: &Rational) -> Result<Rational, Overflow> {
+    a.checked_add(b).ok_or(Overflow)
+}
+
+pub(crate) fn sub(a: &Rational, b: &Rational) -> Result<Rational, Overflow> {
+    a.checked_sub(b).ok_or(Overflow)
+}
+
+pub(crate) fn mul(a: &Rational, b: &Rational) -> Result<Rational, Overflow> {
+    a.checked_mul(b).ok_or(Overflow)
+}
+
+pub(crate) fn div(a: &Rational, b: &Rational) -> Result<Rational, Overflow> {
+    a.checked_div(b).ok_or(Overflow)
+}
+
+// Negating the most negative numerator or denominator overflows too
+fn neg(a: &Rational) -> Result<Rational, Overflow> {
+    sub(&Rational::zero(), a)
+}
+
+/// A dense matrix of exact fractions
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Matrix {
+    rows: Vec<Vec<Rational>>,
+    cols: usize,
+}
+
+/// A matrix in reduced row echelon form, with the column of each non-zero row's leading one
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Echelon {
+    pub matrix: Matrix,
+    pub pivots: Vec<usize>,
+}
+
+/// Every solution of `Ax = b`: `particular` plus any combination of `null_space`.
+///
+/// `null_space[k]` is the direction for free variable `free[k]`, so setting the free
+/// variables to `t` gives `particular + sum(t[k] * null_space[k])`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    pub particular: Vec<Rational>,
+    pub pivots: Vec<usize>,
+    pub free: Vec<usize>,
+    pub null_space: Vec<Vec<Rational>>,
+}
+
+impl Matrix {
+    /// The zero matrix
+    pub fn new(rows: usize, cols: usize) -> Matrix {
+        Matrix {
+            rows: vec![vec![Rational::zero(); cols]; rows],
+            cols,
+        }
+    }
+
+    pub fn identity(size: usize) -> Matrix {
+        let mut matrix = Matrix::new(size, size);
+        for i in 0..size {
+            matrix.rows[i][i] = Rational::one();
+        }
+        matrix
+    }
+
+    pub fn from_rows(rows: Vec<Vec<Rational>>) -> Matrix {
+        let cols = rows.first().map_or(0, |r| r.len());
+        assert!(
+            rows.iter().all(|r| r.len() == cols),
+            "rows must be the same length"
+        );
+        Matrix { rows, cols }
+    }
+
+    pub fn from_integers(rows: &[Vec<i64>]) -> Matrix {
+        Matrix::from_rows(
+            rows.iter()
+                .map(|row| row.iter().map(|a| Rational::from(*a as i128)).collect())
+                .collect(),
+        )
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn col_count(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Rational {
+        self.rows[row][col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: Rational) {
+        self.rows[row][col] = value;
+    }
+
+    pub fn row(&self, row: usize) -> &[Rational] {
+        &self.rows[row]
+    }
+
+    pub fn mul_vector(&self, x: &[Rational]) -> Result<Vec<Rational>, Overflow> {
+        assert_eq!(x.len(), self.cols, "vector must have one entry per column");
+        self.rows.iter().map(|row| dot(row, x)).collect()
+    }
+
+    pub fn mul(&self, other: &Matrix) -> Result<Matrix, Overflow> {
+        assert_eq!(self.cols, other.row_count(), "inner dimensions must match");
+        let mut product = Matrix::new(self.row_count(), other.cols);
+        for (r, row) in self.rows.iter().enumerate() {
+            for c in 0..other.cols {
+                let column = other.rows.iter().map(|o| o[c]).collect::<Vec<_>>();
+                product.rows[r][c] = dot(row, &column)?;
+            }
+        }
+        Ok(product)
+    }
+
+    /// Gauss-Jordan elimination
+    pub fn rref(&self) -> Result<Echelon, Overflow> {
+        let mut rows = self.rows.clone();
+        let (pivots, _) = reduce(&mut rows, self.cols)?;
+        Ok(Echelon {
+            matrix: Matrix::from_rows(rows),
+            pivots,
+        })
+    }
+
+    pub fn rank(&self) -> Result<usize, Overflow> {
+        Ok(self.rref()?.pivots.len())
+    }
+
+    pub fn determinant(&self) -> Result<Rational, Overflow> {
+        assert_eq!(self.row_count(), self.cols, "matrix must be square");
+        let mut rows = self.rows.clone();
+        let (pivots, determinant) = reduce(&mut rows, self.cols)?;
+        if pivots.len() < self.cols {
+            return Ok(Rational::zero());
+        }
+        Ok(determinant)
+    }
+
+    /// The inverse, or `None` if the matrix is singular
+    pub fn inverse(&self) -> Result<Option<Matrix>, Overflow> {
+        assert_eq!(self.row_count(), self.cols, "matrix must be square");
+        let identity = Matrix::identity(self.cols);
+        let mut rows = self
+            .rows
+            .iter()
+            .zip(identity.rows)
+            .map(|(row, unit)| row.iter().copied().chain(unit).collect())
+            .collect::<Vec<Vec<_>>>();
+        let (pivots, _) = reduce(&mut rows, self.cols)?;
+        if pivots.len() < self.cols {
+            return Ok(None);
+        }
+        Ok(Some(Matrix::from_rows(
+            rows.into_iter()
+                .map(|row| row[self.cols..].to_vec())
+                .collect(),
+        )))
+    }
+
+    /// Every solution of `Ax = b`, or `None` if there aren't any
+    pub fn solve(&self, b: &[Rational]) -> Result<Option<Solution>, Overflow> {
+        assert_eq!(b.len(), self.row_count(), "b must have one entry per row");
+        let mut rows = self
+            .rows
+            .iter()
+            .zip(b)
+            .map(|(row, target)| row.iter().chain(Some(target)).copied().collect())
+            .collect::<Vec<Vec<_>>>();
+        let (pivots, _) = reduce(&mut rows, self.cols)?;
+        // Rows without a pivot are all zero, so they can only be satisfied by a zero
+        if rows[pivots.len()..]
+            .iter()
+            .any(|row| !row[self.cols].is_zero())
+        {
+            return Ok(None);
+        }
+        let mut particular = vec![Rational::zero(); self.cols];
+        for (row, col) in pivots.iter().enumerate() {
+            particular[*col] = rows[row][self.cols];
+        }
+        let free = (0..self.cols)
+            .filter(|col| !pivots.contains(col))
+            .collect::<Vec<_>>();
+        let null_space = free
+            .iter()
+            .map(|f| {
+                let mut direction = vec![Rational::zero(); self.cols];
+                direction[*f] = Rational::one();
+                for (row, pivot) in pivots.iter().enumerate() {
+                    direction[*pivot] = neg(&rows[row][*f])?;
+                }
+                Ok(direction)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Some(Solution {
+            particular,
+            pivots,
+            free,
+            null_space,
+        }))
+    }
+}
+
+fn dot(a: &[Rational], b: &[Rational]) -> Result<Rational, Overflow> {
+    a.iter()
+        .zip(b)
+        .try_fold(Rational::zero(), |sum, (x, y)| add(&sum, &mul(x, y)?))
+}
+
+// Reduce the first `cols` columns of rows to reduced row echelon form, carrying any
+// further columns along. Returns the pivot columns, and the determinant of those
+// columns if they're square and every column got a pivot.
+fn reduce(rows: &mut [Vec<Rational>], cols: usize) -> Result<(Vec<usize>, Rational), Overflow> {
+    let mut pivots = vec![];
+    let mut determinant = Rational::one();
+    for col in 0..cols {
+        let rank = pivots.len();
+        let Some(pivot) = (rank..rows.len()).find(|r| !rows[*r][col].is_zero()) else {
+            continue;
+        };
+        if pivot != rank {
+            rows.swap(rank, pivot);
+            determinant = neg(&determinant)?;
+        }
+        let scale = rows[rank][col];
+        determinant = mul(&determinant, &scale)?;
+        for a in rows[rank].iter_mut() {
+            *a = div(a, &scale)?;
+        }
+        let pivot_row = rows[rank].clone();
+        for (r, row) in rows.iter_mut().enumerate() {
+            if r != rank && !row[col].is_zero() {
+                let factor = row[col];
+                for (a, p) in row.iter_mut().zip(&pivot_row).skip(col) {
+                    *a = sub(a, &mul(&factor, p)?)?;
+                }
+            }
+        }
+        pivots.push(col);
+    }
+    Ok((pivots, determinant))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn integers(values: &[i128]) -> Vec<Rational> {
+        values.iter().map(|v| Rational::from(*v)).collect()
+    }
+
+    #[test]
+    fn test_determinant_and_inverse() -> color_eyre::Result<()> {
+        let matrix = Matrix::from_integers(&[vec![2, 1, 0], vec![1, 3, 1], vec![0, 1, 4]]);
+        assert_eq!(matrix.determinant()?, Rational::from(18));
+        let inverse = matrix.inverse()?.unwrap();
+        assert_eq!(inverse.get(0, 0), Rational::new(11, 18));
+        assert_eq!(matrix.mul(&inverse)?, Matrix::identity(3));
+        // Swapping two rows flips the sign
+        let swapped = Matrix::from_integers(&[vec![0, 1], vec![1, 0]]);
+        assert_eq!(swapped.determinant()?, Rational::from(-1));
+        let singular = Matrix::from_integers(&[vec![1, 2], vec![2, 4]]);
+        assert_eq!(singular.determinant()?, Rational::zero());
+        assert_eq!(singular.inverse()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_solve_reports_free_variables() -> color_eyre::Result<()> {
+        // x + y + z = 6, y - z = 1
+        let matrix = Matrix::from_integers(&[vec![1, 1, 1], vec![0, 1, -1]]);
+        let solution = matrix.solve(&integers(&[6, 1]))?.unwrap();
+        assert_eq!(solution.pivots, vec![0, 1]);
+        assert_eq!(solution.free, vec![2]);
+        assert_eq!(solution.particular, integers(&[5, 1, 0]));
+        assert_eq!(solution.null_space, vec![integers(&[-2, 1, 1])]);
+        assert_eq!(
+            matrix.mul_vector(&solution.null_space[0])?,
+            integers(&[0, 0])
+        );
+        assert_eq!(matrix.rank()?, 2);
+        // x + y = 1, x + y = 2 has no solution
+        let matrix = Matrix::from_integers(&[vec![1, 1], vec![1, 1]]);
+        assert_eq!(matrix.solve(&integers(&[1, 2]))?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_overflow() -> color_eyre::Result<()> {
+        let big = Rational::from(i128::MAX / 2);
+        let matrix =
+            Matrix::from_rows(vec![vec![big, Rational::one()], vec![Rational::one(), big]]);
+        assert_eq!(matrix.determinant(), Err(Overflow));
+        // Negations are checked too: a row swap after a pivot of i128::MIN, and a free
+        // coefficient of i128::MIN in the null space
+        let min = Rational::from(i128::MIN);
+        let mut swapped = Matrix::identity(3);
+        swapped.set(0, 0, min);
+        swapped.set(1, 1, Rational::zero());
+        swapped.set(1, 2, Rational::one());
+        swapped.set(2, 1, Rational::one());
+        swapped.set(2, 2, Rational::zero());
+        assert_eq!(swapped.determinant(), Err(Overflow));
+        let matrix = Matrix::from_rows(vec![vec![Rational::one(), min]]);
+        assert_eq!(matrix.solve(&integers(&[0])), Err(Overflow));
+        Ok(())
+    }
+}
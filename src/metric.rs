@@ -0,0 +1,112 @@
+use std::cmp::Ordering;
+
+/// Squared Euclidean distance between two points.
+///
+/// This is exact, unlike a rounded square root, and orders pairs of points the same way
+/// as the true distance does, so it's the one to sort by.
+///
+/// Each axis can be anything an `i64` holds, but the total has to fit in a `u128`, which
+/// is always true for up to four axes with coordinates strictly inside `±2^62`. `None` if
+/// it doesn't.
+pub fn squared_euclidean<const K: usize>(a: &[i64; K], b: &[i64; K]) -> Option<u128> {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x.abs_diff(*y) as u128).pow(2))
+        .try_fold(0u128, |total, square| total.checked_add(square))
+}
+
+/// Sum of the distances along each axis
+pub fn manhattan<const K: usize>(a: &[i64; K], b: &[i64; K]) -> u128 {
+    a.iter().zip(b).map(|(x, y)| x.abs_diff(*y) as u128).sum()
+}
+
+/// Largest distance along any one axis
+pub fn chebyshev<const K: usize>(a: &[i64; K], b: &[i64; K]) -> u128 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| x.abs_diff(*y) as u128)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Euclidean distance, for when the actual length is needed rather than just an ordering
+pub fn euclidean<const K: usize>(a: &[i64; K], b: &[i64; K]) -> OrderedFloat {
+    // Summed as floats, so this never overflows
+    let total = a
+        .iter()
+        .zip(b)
+        .map(|(x, y)| (x.abs_diff(*y) as f64).powi(2))
+        .sum::<f64>();
+    OrderedFloat(total.sqrt())
+}
+
+/// An `f64` with a total order, so it can be used as a sort key
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OrderedFloat(pub f64);
+
+impl PartialEq for OrderedFloat {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OrderedFloat {}
+
+impl PartialOrd for OrderedFloat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedFloat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics() -> color_eyre::Result<()> {
+        let a = [1, -2, 3];
+        let b = [4, 2, 3];
+        assert_eq!(squared_euclidean(&a, &b), Some(25));
+        assert_eq!(manhattan(&a, &b), 7);
+        assert_eq!(chebyshev(&a, &b), 4);
+        assert_eq!(euclidean(&a, &b), OrderedFloat(5.0));
+        assert_eq!(
+            squared_euclidean(&[i64::MIN], &[i64::MAX]),
+            Some((u64::MAX as u128).pow(2))
+        );
+        // The widest four-dimensional range that's guaranteed to fit
+        let limit = (1 << 62) - 1;
+        assert_eq!(
+            squared_euclidean(&[-limit; 4], &[limit; 4]),
+            Some(4 * (2 * limit as u128).pow(2))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_squared_euclidean_overflow() -> color_eyre::Result<()> {
+        let (low, high) = ([i64::MIN, i64::MIN], [i64::MAX, i64::MAX]);
+        assert_eq!(squared_euclidean(&low, &high), None);
+        // The rounded distance is still available
+        assert_eq!(
+            euclidean(&low, &high),
+            OrderedFloat(2f64.powi(64) * 2f64.sqrt())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_squared_euclidean_separates_rounded_ties() -> color_eyre::Result<()> {
+        // Both of these round down to 3 with an integer square root
+        let origin = [0, 0, 0];
+        assert!(squared_euclidean(&origin, &[3, 0, 0]) < squared_euclidean(&origin, &[3, 1, 1]));
+        assert!(OrderedFloat(f64::NAN) > OrderedFloat(f64::INFINITY));
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+use std::fmt::{self, Display};
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use num::Integer;
+
+/// An integer modulo `M`, always kept in `0..M`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ModInt<const M: u64> {
+    value: u64,
+}
+
+/// A modulus only known at runtime, for the same arithmetic as `ModInt` on plain `u64`s
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Modulus(pub u64);
+
+impl Modulus {
+    /// `value` brought into `0..m`, counting down from zero for negatives
+    pub fn reduce(&self, value: i128) -> u64 {
+        value.rem_euclid(self.0 as i128) as u64
+    }
+
+    pub fn add(&self, a: u64, b: u64) -> u64 {
+        ((a as u128 + b as u128) % self.0 as u128) as u64
+    }
+
+    pub fn sub(&self, a: u64, b: u64) -> u64 {
+        self.reduce(a as i128 - b as i128)
+    }
+
+    pub fn mul(&self, a: u64, b: u64) -> u64 {
+        ((a as u128 * b as u128) % self.0 as u128) as u64
+    }
+
+    /// `base` to the power `exponent` by repeated squaring
+    pub fn pow(&self, base: u64, mut exponent: u64) -> u64 {
+        let mut base = base % self.0;
+        let mut result = 1 % self.0;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = self.mul(result, base);
+            }
+            base = self.mul(base, base);
+            exponent >>= 1;
+        }
+        result
+    }
+
+    /// The `x` with `a * x = 1`, which exists when `a` and the modulus are coprime
+    pub fn inverse(&self, a: u64) -> Option<u64> {
+        let (g, x, _) = extended_gcd(a as i128, self.0 as i128);
+        (g == 1).then(|| self.reduce(x))
+    }
+}
+
+impl<const M: u64> ModInt<M> {
+    pub const MODULUS: Modulus = Modulus(M);
+
+    pub fn new(value: i64) -> ModInt<M> {
+        ModInt {
+            value: Self::MODULUS.reduce(value as i128),
+        }
+    }
+
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    pub fn pow(&self, exponent: u64) -> ModInt<M> {
+        ModInt {
+            value: Self::MODULUS.pow(self.value, exponent),
+        }
+    }
+
+    pub fn inverse(&self) -> Option<ModInt<M>> {
+        Self::MODULUS
+            .inverse(self.value)
+            .map(|value| ModInt { value })
+    }
+}
+
+impl<const M: u64> From<u64> for ModInt<M> {
+    fn from(value: u64) -> Self {
+        ModInt { value: value % M }
+    }
+}
+
+impl<const M: u64> Display for ModInt<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl<const M: u64> Add for ModInt<M> {
+    type Output = ModInt<M>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        ModInt {
+            value: Self::MODULUS.add(self.value, rhs.value),
+        }
+    }
+}
+
+impl<const M: u64> Sub for ModInt<M> {
+    type Output = ModInt<M>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        ModInt {
+            value: Self::MODULUS.sub(self.value, rhs.value),
+        }
+    }
+}
+
+impl<const M: u64> Mul for ModInt<M> {
+    type Output = ModInt<M>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        ModInt {
+            value: Self::MODULUS.mul(self.value, rhs.value),
+        }
+    }
+}
+
+impl<const M: u64> Neg for ModInt<M> {
+    type Output = ModInt<M>;
+
+    fn neg(self) -> Self::Output {
+        ModInt::default() - self
+    }
+}
+
+/// Adding a plain integer, which may be negative, e.g. turning a dial either way
+impl<const M: u64> Add<i64> for ModInt<M> {
+    type Output = ModInt<M>;
+
+    fn add(self, rhs: i64) -> Self::Output {
+        ModInt {
+            value: Self::MODULUS.reduce(self.value as i128 + rhs as i128),
+        }
+    }
+}
+
+impl<const M: u64> AddAssign for ModInt<M> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<const M: u64> AddAssign<i64> for ModInt<M> {
+    fn add_assign(&mut self, rhs: i64) {
+        *self = *self + rhs;
+    }
+}
+
+impl<const M: u64> SubAssign for ModInt<M> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<const M: u64> MulAssign for ModInt<M> {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+/// Division rounding towards negative infinity, so `floor_div(-1, 100)` is -1, not 0
+pub fn floor_div<T: Integer>(a: T, b: T) -> T {
+    a.div_floor(&b)
+}
+
+/// How many multiples of `m` a walk from `start` to `start + distance` lands on, one
+/// step at a time in either direction. The start doesn't count, but the end does.
+pub fn multiples_crossed(start: i64, distance: i64, m: i64) -> u64 {
+    let (lower, upper) = if distance >= 0 {
+        (start, start + distance)
+    } else {
+        (start + distance - 1, start - 1)
+    };
+    (floor_div(upper, m) - floor_div(lower, m)) as u64
+}
+
+/// `(g, x, y)` with `g = gcd(a, b) = a * x + b * y`
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a.rem_euclid(b));
+        (g, y, x - a.div_euclid(b) * y)
+    }
+}
+
+/// The `x` satisfying every `x = residue (mod modulus)`, as a residue modulo the lcm of
+/// the moduli, or `None` if they contradict each other. Moduli needn't be coprime.
+///
+/// Also `None` if the lcm doesn't fit in a `u64`, since the answer couldn't be returned.
+/// Every step is checked, so that's reported rather than silently truncated.
+pub fn chinese_remainder(congruences: &[(i64, u64)]) -> Option<(u64, u64)> {
+    let mut residue: i128 = 0;
+    let mut modulus: i128 = 1;
+    for (r, m) in congruences {
+        let (r, m) = (*r as i128, *m as i128);
+        // Solve residue + modulus * k = r (mod m) for k
+        let (g, p, _) = extended_gcd(modulus, m);
+        if (r - residue) % g != 0 {
+            return None;
+        }
+        let step = m / g;
+        // Both factors are below `step`, which fits in a u64, so their product fits in a u128
+        let k = Modulus(step as u64).mul(
+            ((r - residue) / g).rem_euclid(step) as u64,
+            p.rem_euclid(step) as u64,
+        ) as i128;
+        // The new modulus is the lcm so far, and it must fit in a u64 to be returned. Then
+        // the old modulus times `k`, which is less than `step`, fits too.
+        modulus = modulus
+            .checked_mul(step)
+            .filter(|lcm| u64::try_from(*lcm).is_ok())?;
+        residue = (residue + (modulus / step) * k).rem_euclid(modulus);
+    }
+    Some((residue as u64, modulus as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mod_int() -> color_eyre::Result<()> {
+        type Dial = ModInt<100>;
+        let mut dial = Dial::new(50);
+        dial += -68;
+        assert_eq!(dial.value(), 82);
+        dial += 1018;
+        assert_eq!(dial, Dial::new(0));
+        assert_eq!((-Dial::new(1)).value(), 99);
+        assert_eq!(ModInt::<7>::new(3).pow(6), ModInt::new(1));
+        assert_eq!(ModInt::<7>::new(3).inverse(), Some(ModInt::new(5)));
+        assert_eq!(Dial::new(10).inverse(), None);
+        let big = ModInt::<1_000_000_007>::new(-1);
+        assert_eq!((big * big).value(), 1);
+        assert_eq!(Modulus(13).pow(2, 100), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiples_crossed() -> color_eyre::Result<()> {
+        assert_eq!(floor_div(-1, 100), -1);
+        assert_eq!(multiples_crossed(50, -68, 100), 1);
+        assert_eq!(multiples_crossed(82, -30, 100), 0);
+        assert_eq!(multiples_crossed(52, 48, 100), 1);
+        assert_eq!(multiples_crossed(0, -5, 100), 0);
+        assert_eq!(multiples_crossed(0, 5, 100), 0);
+        assert_eq!(multiples_crossed(50, 1000, 100), 10);
+        assert_eq!(multiples_crossed(5, -1005, 100), 11);
+        // The same as stepping one at a time
+        for start in -20..20 {
+            for distance in -50i64..50 {
+                let (step, count) = (distance.signum(), distance.abs());
+                let brute = (1..=count).filter(|i| (start + step * i) % 7 == 0).count();
+                assert_eq!(multiples_crossed(start, distance, 7), brute as u64);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_chinese_remainder() -> color_eyre::Result<()> {
+        assert_eq!(
+            chinese_remainder(&[(2, 3), (3, 5), (2, 7)]),
+            Some((23, 105))
+        );
+        assert_eq!(chinese_remainder(&[(1, 4), (3, 6)]), Some((9, 12)));
+        assert_eq!(chinese_remainder(&[(0, 4), (1, 6)]), None);
+        assert_eq!(chinese_remainder(&[(-1, 5)]), Some((4, 5)));
+        assert_eq!(extended_gcd(240, 46), (2, -9, 47));
+        // Two large coprime moduli whose lcm only just fits in a u64
+        let (p, q) = (4_294_967_291u64, 4_294_967_279u64);
+        let (x, lcm) = chinese_remainder(&[(1, p), (2, q)]).unwrap();
+        assert_eq!(lcm, p * q);
+        assert_eq!((x % p, x % q), (1, 2));
+        // A third prime takes the lcm past u64
+        assert_eq!(chinese_remainder(&[(1, p), (2, q), (3, 65_537)]), None);
+        assert_eq!(chinese_remainder(&[(0, u64::MAX), (1, u64::MAX - 1)]), None);
+        assert_eq!(
+            chinese_remainder(&[(0, 3), (-1, u64::MAX / 3)]),
+            Some((u64::MAX / 3 * 2 - 1, u64::MAX))
+        );
+        Ok(())
+    }
+}
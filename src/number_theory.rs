@@ -0,0 +1,258 @@
+use itertools::Itertools;
+use num::Integer;
+use num::integer::gcd;
+
+use crate::modular::Modulus;
+
+/// The gcd of every value, or zero if there aren't any
+pub fn gcd_all<T: Integer + Copy>(values: impl IntoIterator<Item = T>) -> T {
+    values.into_iter().fold(T::zero(), |acc, x| acc.gcd(&x))
+}
+
+/// The lcm of every value, or one if there aren't any
+pub fn lcm_all<T: Integer + Copy>(values: impl IntoIterator<Item = T>) -> T {
+    values.into_iter().fold(T::one(), |acc, x| acc.lcm(&x))
+}
+
+/// Every divisor of `n`, smallest first. Panics for zero, which has infinitely many.
+pub fn divisors(n: u64) -> Vec<u64> {
+    let mut divisors = vec![1];
+    for (prime, power) in factorise(n) {
+        let smaller = divisors.clone();
+        let mut multiplier = 1;
+        for _ in 0..power {
+            multiplier *= prime;
+            divisors.extend(smaller.iter().map(|d| d * multiplier));
+        }
+    }
+    divisors.sort();
+    divisors
+}
+
+/// Every prime up to and including `limit`, by the sieve of Eratosthenes
+pub fn primes_up_to(limit: usize) -> Vec<u64> {
+    let mut composite = vec![false; limit + 1];
+    let mut primes = vec![];
+    for n in 2..=limit {
+        if composite[n] {
+            continue;
+        }
+        primes.push(n as u64);
+        for multiple in (n * n..=limit).step_by(n) {
+            composite[multiple] = true;
+        }
+    }
+    primes
+}
+
+/// Deterministic Miller-Rabin. These bases are enough for every `u64`.
+pub fn is_prime(n: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    if let Some(base) = BASES.iter().find(|b| n.is_multiple_of(**b)) {
+        return n == *base;
+    }
+    let modulus = Modulus(n);
+    let twos = (n - 1).trailing_zeros();
+    let odd = (n - 1) >> twos;
+    BASES.iter().all(|base| {
+        let mut x = modulus.pow(*base, odd);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        for _ in 1..twos {
+            x = modulus.mul(x, x);
+            if x == n - 1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// The prime factorisation of `n` as `(prime, power)` pairs, smallest prime first.
+///
+/// Small factors are found by trial division and anything left over by Pollard's rho,
+/// so this is fast for any `u64`. Panics for zero, which has no factorisation.
+pub fn factorise(n: u64) -> Vec<(u64, u32)> {
+    assert!(n > 0, "zero has no prime factorisation");
+    let mut primes = vec![];
+    let mut rest = n;
+    for p in 2..1000 {
+        while rest.is_multiple_of(p) {
+            primes.push(p);
+            rest /= p;
+        }
+    }
+    let mut pending = vec![rest];
+    while let Some(m) = pending.pop() {
+        if m == 1 {
+            continue;
+        }
+        if is_prime(m) {
+            primes.push(m);
+        } else {
+            let factor = pollard_rho(m);
+            pending.push(factor);
+            pending.push(m / factor);
+        }
+    }
+    primes
+        .into_iter()
+        .sorted()
+        .dedup_with_count()
+        .map(|(count, prime)| (prime, count as u32))
+        .collect()
+}
+
+/// The prime factorisation of `n` by trial division alone, which is simpler but takes
+/// O(sqrt(n)) steps. Panics for zero, like `factorise`.
+pub fn trial_factorise(mut n: u64) -> Vec<(u64, u32)> {
+    assert!(n > 0, "zero has no prime factorisation");
+    let mut factors = vec![];
+    let mut p = 2;
+    // Dividing rather than squaring, which would overflow for primes near u64::MAX
+    while p <= n / p {
+        let mut power = 0;
+        while n.is_multiple_of(p) {
+            n /= p;
+            power += 1;
+        }
+        if power > 0 {
+            factors.push((p, power));
+        }
+        p += 1;
+    }
+    if n > 1 {
+        factors.push((n, 1));
+    }
+    factors
+}
+
+/// The number of values in `1..=n` coprime to `n`. Panics for zero.
+pub fn totient(n: u64) -> u64 {
+    factorise(n)
+        .into_iter()
+        .fold(n, |acc, (prime, _)| acc / prime * (prime - 1))
+}
+
+/// The Möbius function: 0 if `n` has a squared factor, otherwise 1 or -1 for an even or
+/// odd number of prime factors. Its sum over the divisors of `n` is 0 for every `n > 1`,
+/// which is what makes it useful for inclusion-exclusion over divisors. Panics for zero.
+pub fn mobius(n: u64) -> i8 {
+    let factors = factorise(n);
+    if factors.iter().any(|(_, power)| *power > 1) {
+        0
+    } else if factors.len().is_multiple_of(2) {
+        1
+    } else {
+        -1
+    }
+}
+
+// Find a non-trivial factor of a composite `n` with Brent's variant of Pollard's rho,
+// trying a new constant if one walk collapses
+fn pollard_rho(n: u64) -> u64 {
+    if n.is_multiple_of(2) {
+        return 2;
+    }
+    let modulus = Modulus(n);
+    for c in 1.. {
+        let f = |x: u64| modulus.add(modulus.mul(x, x), c);
+        let (mut y, mut factor) = (2, 1);
+        let mut power = 1;
+        while factor == 1 {
+            let x = y;
+            for _ in 0..power {
+                y = f(y);
+            }
+            let mut k = 0;
+            while k < power && factor == 1 {
+                // Batch the gcds by multiplying the differences together
+                let mut product = 1;
+                let mut last = y;
+                for _ in 0..(power - k).min(128) {
+                    last = y;
+                    y = f(y);
+                    product = modulus.mul(product, x.abs_diff(y));
+                }
+                factor = gcd(product, n);
+                if factor == n {
+                    // The batch overshot, so step back through it one at a time
+                    y = last;
+                    loop {
+                        y = f(y);
+                        factor = gcd(x.abs_diff(y), n);
+                        if factor > 1 {
+                            break;
+                        }
+                    }
+                }
+                k += 128;
+            }
+            power *= 2;
+        }
+        if factor != n {
+            return factor;
+        }
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcd_lcm() -> color_eyre::Result<()> {
+        assert_eq!(gcd_all([12, 18, 30]), 6);
+        assert_eq!(lcm_all([4u64, 6, 10]), 60);
+        assert_eq!(gcd_all(Vec::<u32>::new()), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_primes_and_factors() -> color_eyre::Result<()> {
+        assert_eq!(primes_up_to(30), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert!((0..1000).all(|n| is_prime(n) == primes_up_to(1000).contains(&n)));
+        assert!(is_prime(1_000_000_007));
+        assert!(!is_prime(3_215_031_751));
+        assert_eq!(divisors(12), vec![1, 2, 3, 4, 6, 12]);
+        assert_eq!(factorise(360), vec![(2, 3), (3, 2), (5, 1)]);
+        assert_eq!(factorise(1), vec![]);
+        // Two large primes, beyond trial division
+        let n = 1_000_000_007 * 998_244_353;
+        assert_eq!(factorise(n), vec![(998_244_353, 1), (1_000_000_007, 1)]);
+        assert_eq!(factorise(u64::MAX), trial_factorise(u64::MAX));
+        for n in 1..2000 {
+            assert_eq!(factorise(n), trial_factorise(n));
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "zero has no prime factorisation")]
+    fn test_divisors_of_zero() {
+        divisors(0);
+    }
+
+    #[test]
+    fn test_totient_and_mobius() -> color_eyre::Result<()> {
+        assert_eq!(totient(36), 12);
+        assert_eq!(totient(97), 96);
+        assert_eq!(
+            (1..=10).map(mobius).collect_vec(),
+            vec![1, -1, -1, 0, -1, 1, -1, 0, 0, 1]
+        );
+        assert!((2..200).all(|n| {
+            divisors(n)
+                .into_iter()
+                .map(|d| mobius(d) as i32)
+                .sum::<i32>()
+                == 0
+        }));
+        Ok(())
+    }
+}
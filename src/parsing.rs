@@ -1,35 +1,294 @@
-use std::str::FromStr;
-
 use nom::{
-    IResult,
+    Err, IResult,
     bytes::complete::tag,
-    character::complete::{char, digit1, space1},
-    combinator::{map, map_res, opt},
-    multi::separated_list1,
-    sequence::{pair, separated_pair},
+    character::complete::{satisfy, space0, space1},
+    combinator::{map, opt},
+    error::{Error, ErrorKind},
+    multi::{many1, separated_list1},
+    sequence::{delimited, pair, preceded, separated_pair},
 };
 use num::PrimInt;
 
 use crate::range::Range;
 
-pub fn parse_range<T: PrimInt + FromStr>(input: &str) -> IResult<&str, Range<T>> {
+pub fn parse_range<T: PrimInt>(input: &str) -> IResult<&str, Range<T>> {
     map(
         separated_pair(parse_number, tag("-"), parse_number),
         |(lower, upper)| Range { lower, upper },
     )(input)
 }
 
-pub fn parse_number<T: FromStr>(input: &str) -> IResult<&str, T> {
-    map_res(
-        pair(opt(char('-')), digit1),
-        |(sign, x): (_, &str)| match sign {
-            Some(_) => ("-".to_string() + x).parse::<T>(),
-            None => x.parse::<T>(),
-        },
-    )(input)
+/// Parse a decimal number with an optional `+` or `-` sign.
+///
+/// A value that doesn't fit in `T` (including a negative value for an unsigned `T`)
+/// is a `Failure` with `ErrorKind::TooLarge`, rather than being silently truncated.
+pub fn parse_number<T: PrimInt>(input: &str) -> IResult<&str, T> {
+    parse_number_radix(10)(input)
+}
+
+/// Parse an unsigned decimal number, without accepting any sign
+pub fn parse_unsigned<T: PrimInt>(input: &str) -> IResult<&str, T> {
+    accumulate_digits(input, 10, false, None)
+}
+
+/// Parse a number with an optional sign in the given radix, e.g. `parse_number_radix::<u32>(16)`.
+/// Panics straight away if the radix isn't in `2..=36`, like `char::to_digit` would on the
+/// first digit.
+pub fn parse_number_radix<T: PrimInt>(radix: u32) -> impl Fn(&str) -> IResult<&str, T> {
+    assert!(
+        (2..=36).contains(&radix),
+        "radix must be in 2..=36, not {radix}"
+    );
+    move |input| parse_signed_digits(input, radix, None)
+}
+
+/// Parse a decimal number whose digits may be grouped by `separator`, e.g. `1_000_000`.
+/// The separator is only consumed between two digits.
+pub fn parse_number_grouped<T: PrimInt>(separator: char) -> impl Fn(&str) -> IResult<&str, T> {
+    move |input| parse_signed_digits(input, 10, Some(separator))
+}
+
+/// Parse a string of `0` and `1`, without any `0b` prefix
+pub fn parse_binary<T: PrimInt>(input: &str) -> IResult<&str, T> {
+    accumulate_digits(input, 2, false, None)
+}
+
+/// Parse a string of hex digits in either case, without any `0x` prefix
+pub fn parse_hex<T: PrimInt>(input: &str) -> IResult<&str, T> {
+    accumulate_digits(input, 16, false, None)
+}
+
+/// Parse a single decimal digit
+pub fn parse_digit(input: &str) -> IResult<&str, u8> {
+    map(satisfy(|c| c.is_ascii_digit()), |c| c as u8 - b'0')(input)
+}
+
+/// Parse a run of decimal digits into their individual values, e.g. `"987"` -> `[9, 8, 7]`
+pub fn parse_digits(input: &str) -> IResult<&str, Vec<u8>> {
+    many1(parse_digit)(input)
 }
 
 /// Parse a list of whitespace separated numbers
-pub fn parse_numbers<T: FromStr>(input: &str) -> IResult<&str, Vec<T>> {
+pub fn parse_numbers<T: PrimInt>(input: &str) -> IResult<&str, Vec<T>> {
     separated_list1(space1, parse_number)(input)
 }
+
+/// Parse `separator`, allowing spaces or tabs on either side of it.
+/// A separator that is only whitespace matches any run of spaces or tabs.
+pub fn parse_separator<'a>(separator: &'a str) -> impl Fn(&'a str) -> IResult<&'a str, &'a str> {
+    let trimmed = separator.trim();
+    move |input| {
+        if trimmed.is_empty() {
+            space1(input)
+        } else {
+            delimited(space0, tag(trimmed), space0)(input)
+        }
+    }
+}
+
+/// Parse a list of numbers separated by `separator`, with flexible whitespace around each separator
+pub fn parse_list<'a, T: PrimInt>(
+    separator: &'a str,
+) -> impl Fn(&'a str) -> IResult<&'a str, Vec<T>> {
+    move |input| separated_list1(parse_separator(separator), parse_number)(input)
+}
+
+/// Parse exactly `N` numbers separated by `separator`, e.g. `parse_tuple::<i64, 3>(",")` for `1,2,3`.
+///
+/// Too few numbers, or a separator followed by another number after the `N`th,
+/// is an error with `ErrorKind::Count` rather than a short or truncated array.
+pub fn parse_tuple<'a, T: PrimInt, const N: usize>(
+    separator: &'a str,
+) -> impl Fn(&'a str) -> IResult<&'a str, [T; N]> {
+    move |input| {
+        let count_error = || Err::Error(Error::new(input, ErrorKind::Count));
+        let mut values = [T::zero(); N];
+        let mut rest = input;
+        for (i, value) in values.iter_mut().enumerate() {
+            if i > 0 {
+                rest = parse_separator(separator)(rest)
+                    .map_err(|_| count_error())?
+                    .0;
+            }
+            (rest, *value) = parse_number(rest)?;
+        }
+        if preceded(parse_separator(separator), parse_number::<T>)(rest).is_ok() {
+            return Err(count_error());
+        }
+        Ok((rest, values))
+    }
+}
+
+/// Parse `inner` between `open` and `close`, allowing whitespace just inside the brackets
+pub fn parse_bracketed<'a, O, F>(
+    open: &'a str,
+    close: &'a str,
+    inner: F,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O>
+where
+    F: FnMut(&'a str) -> IResult<&'a str, O>,
+{
+    delimited(pair(tag(open), space0), inner, pair(space0, tag(close)))
+}
+
+/// Parse a bracketed list of numbers such as `(0,3,4)` or `{ 3, 5, 4 }`
+pub fn parse_bracketed_list<'a, T: PrimInt>(
+    open: &'a str,
+    separator: &'a str,
+    close: &'a str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<T>> {
+    parse_bracketed(open, close, parse_list(separator))
+}
+
+fn parse_signed_digits<T: PrimInt>(
+    input: &str,
+    radix: u32,
+    separator: Option<char>,
+) -> IResult<&str, T> {
+    let (rest, sign) = opt(satisfy(|c| c == '+' || c == '-'))(input)?;
+    match accumulate_digits(rest, radix, sign == Some('-'), separator) {
+        // Report overflow from the start of the number, including the sign
+        Err(Err::Failure(e)) if e.code == ErrorKind::TooLarge => {
+            Err(Err::Failure(Error::new(input, ErrorKind::TooLarge)))
+        }
+        result => result,
+    }
+}
+
+// Build the value up one digit at a time, subtracting rather than adding when negative
+// so that `T::min_value()` can be parsed without overflowing on the way
+fn accumulate_digits<T: PrimInt>(
+    input: &str,
+    radix: u32,
+    negative: bool,
+    separator: Option<char>,
+) -> IResult<&str, T> {
+    let too_large = || Err::Failure(Error::new(input, ErrorKind::TooLarge));
+    // Any radix up to 36 fits in every PrimInt, even i8
+    let base = T::from(radix).ok_or_else(too_large)?;
+    let mut value = T::zero();
+    let mut consumed = 0;
+    let mut chars = input.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if let Some(digit) = c.to_digit(radix) {
+            let digit = T::from(digit).ok_or_else(too_large)?;
+            let shifted = value.checked_mul(&base).ok_or_else(too_large)?;
+            value = if negative {
+                shifted.checked_sub(&digit)
+            } else {
+                shifted.checked_add(&digit)
+            }
+            .ok_or_else(too_large)?;
+            consumed = index + c.len_utf8();
+        } else if Some(c) == separator
+            && consumed == index
+            && consumed > 0
+            && chars.peek().is_some_and(|(_, next)| next.is_digit(radix))
+        {
+            continue;
+        } else {
+            break;
+        }
+    }
+    if consumed == 0 {
+        return Err(Err::Error(Error::new(input, ErrorKind::Digit)));
+    }
+    Ok((&input[consumed..], value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_number_signs() -> color_eyre::Result<()> {
+        assert_eq!(parse_number::<i32>("-12,3"), Ok((",3", -12)));
+        assert_eq!(parse_number::<i32>("+12"), Ok(("", 12)));
+        assert_eq!(parse_number::<u64>("12-34"), Ok(("-34", 12)));
+        assert_eq!(parse_number::<i8>("-128"), Ok(("", i8::MIN)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_number_overflow() -> color_eyre::Result<()> {
+        assert_eq!(
+            parse_number::<u8>("256"),
+            Err(Err::Failure(Error::new("256", ErrorKind::TooLarge)))
+        );
+        assert_eq!(
+            parse_number::<i8>("-129"),
+            Err(Err::Failure(Error::new("-129", ErrorKind::TooLarge)))
+        );
+        assert_eq!(
+            parse_number::<u32>("-1"),
+            Err(Err::Failure(Error::new("-1", ErrorKind::TooLarge)))
+        );
+        assert!(matches!(parse_unsigned::<u32>("-1"), Err(Err::Error(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_radix() -> color_eyre::Result<()> {
+        assert_eq!(parse_binary::<u8>("1011 "), Ok((" ", 11)));
+        assert_eq!(parse_hex::<u32>("fF"), Ok(("", 255)));
+        assert_eq!(parse_number_radix::<i32>(16)("-1a"), Ok(("", -26)));
+        assert_eq!(parse_number_radix::<u64>(36)("zz"), Ok(("", 1295)));
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "radix must be in 2..=36")]
+    fn test_parse_radix_out_of_range() {
+        let _ = parse_number_radix::<u32>(40);
+    }
+
+    #[test]
+    fn test_parse_grouped() -> color_eyre::Result<()> {
+        assert_eq!(
+            parse_number_grouped::<u32>('_')("1_000_000"),
+            Ok(("", 1_000_000))
+        );
+        assert_eq!(parse_number_grouped::<u32>(',')("12,a"), Ok((",a", 12)));
+        assert_eq!(parse_number_grouped::<u32>(',')("12,,3"), Ok((",,3", 12)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_tuple() -> color_eyre::Result<()> {
+        assert_eq!(
+            parse_tuple::<i64, 3>(",")("1,-2,3 x"),
+            Ok((" x", [1, -2, 3]))
+        );
+        assert_eq!(parse_tuple::<i64, 2>(",")("1 , 2"), Ok(("", [1, 2])));
+        assert_eq!(
+            parse_tuple::<i64, 3>(",")("1,2"),
+            Err(Err::Error(Error::new("1,2", ErrorKind::Count)))
+        );
+        assert_eq!(
+            parse_tuple::<i64, 2>(",")("1,2,3"),
+            Err(Err::Error(Error::new("1,2,3", ErrorKind::Count)))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_lists() -> color_eyre::Result<()> {
+        assert_eq!(parse_list::<u32>(" ")("1  2\t3"), Ok(("", vec![1, 2, 3])));
+        assert_eq!(parse_list::<u32>(",")("1, 2 ,3,"), Ok((",", vec![1, 2, 3])));
+        assert_eq!(
+            parse_bracketed_list::<usize>("(", ",", ")")("(0,3,4) (1)"),
+            Ok((" (1)", vec![0, 3, 4]))
+        );
+        assert_eq!(
+            parse_bracketed_list::<u32>("{", ",", "}")("{ 3, 5 }"),
+            Ok(("", vec![3, 5]))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_digits() -> color_eyre::Result<()> {
+        assert_eq!(parse_digits("9870x"), Ok(("x", vec![9, 8, 7, 0])));
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use std::ops::{Add, Sub};
+
+use num::Zero;
+
+use crate::coordinate::IndexingCoordinate;
+use crate::range::Range;
+
+/// Running totals of a sequence, for O(1) sums over any range of it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrefixSums<T> {
+    // sums[i] is the total of the first i values
+    sums: Vec<T>,
+}
+
+/// A summed-area table: running totals over a grid, for O(1) sums over any rectangle.
+///
+/// Grids here are the usual `Vec<Vec<V>>`, indexed by `IndexingCoordinate`, and `value`
+/// turns each cell into the number to sum, so a grid of booleans or enums can be
+/// counted with something like `|cell| *cell as usize`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SummedAreaTable<T> {
+    // sums[r][c] is the total of the cells in rows ..r and columns ..c
+    sums: Vec<Vec<T>>,
+}
+
+impl<T> PrefixSums<T>
+where
+    T: Copy + Zero + Add<Output = T> + Sub<Output = T>,
+{
+    pub fn new(values: &[T]) -> PrefixSums<T> {
+        let mut sums = Vec::with_capacity(values.len() + 1);
+        sums.push(T::zero());
+        for value in values {
+            sums.push(*sums.last().unwrap() + *value);
+        }
+        PrefixSums { sums }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sums.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The total of the values at indices `start..end`
+    pub fn sum(&self, start: usize, end: usize) -> T {
+        self.sums[end] - self.sums[start]
+    }
+
+    /// The total of the values at indices in the inclusive `range`
+    pub fn sum_range(&self, range: &Range<usize>) -> T {
+        self.sum(range.lower, range.upper + 1)
+    }
+}
+
+impl<T> SummedAreaTable<T>
+where
+    T: Copy + Zero + Add<Output = T> + Sub<Output = T>,
+{
+    pub fn new<V>(grid: &[Vec<V>], value: impl Fn(&V) -> T) -> SummedAreaTable<T> {
+        let cols = grid.first().map_or(0, |row| row.len());
+        let mut sums = vec![vec![T::zero(); cols + 1]; grid.len() + 1];
+        for (r, row) in grid.iter().enumerate() {
+            assert_eq!(row.len(), cols, "rows must be the same length");
+            let mut row_total = T::zero();
+            for (c, cell) in row.iter().enumerate() {
+                row_total = row_total + value(cell);
+                sums[r + 1][c + 1] = sums[r][c + 1] + row_total;
+            }
+        }
+        SummedAreaTable { sums }
+    }
+
+    /// A table over a grid that already holds the numbers to sum
+    pub fn from_grid(grid: &[Vec<T>]) -> SummedAreaTable<T> {
+        SummedAreaTable::new(grid, |cell| *cell)
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.sums.len() - 1
+    }
+
+    pub fn col_count(&self) -> usize {
+        self.sums[0].len() - 1
+    }
+
+    /// The total of the rectangle with corners `top_left` and `bottom_right`, inclusive
+    pub fn sum(&self, top_left: IndexingCoordinate, bottom_right: IndexingCoordinate) -> T {
+        let (top, left) = (top_left.row, top_left.col);
+        let (bottom, right) = (bottom_right.row + 1, bottom_right.col + 1);
+        // Subtract whole strips so unsigned totals never go negative on the way
+        (self.sums[bottom][right] - self.sums[top][right])
+            - (self.sums[bottom][left] - self.sums[top][left])
+    }
+
+    /// The total of the cells within `radius` of `centre` in both directions, clipped
+    /// to the grid. With a radius of 1 that's the cell and its eight neighbours. An empty
+    /// grid has nothing around any cell, so its total is zero.
+    pub fn sum_around(&self, centre: IndexingCoordinate, radius: usize) -> T {
+        if self.row_count() == 0 || self.col_count() == 0 {
+            return T::zero();
+        }
+        let top_left = IndexingCoordinate::new(
+            centre.row.saturating_sub(radius),
+            centre.col.saturating_sub(radius),
+        );
+        let bottom_right = IndexingCoordinate::new(
+            centre.row.saturating_add(radius).min(self.row_count() - 1),
+            centre.col.saturating_add(radius).min(self.col_count() - 1),
+        );
+        self.sum(top_left, bottom_right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_sums() -> color_eyre::Result<()> {
+        let sums = PrefixSums::new(&[3u32, 1, 4, 1, 5]);
+        assert_eq!(sums.len(), 5);
+        assert_eq!(sums.sum(0, 5), 14);
+        assert_eq!(sums.sum(2, 2), 0);
+        assert_eq!(sums.sum_range(&Range { lower: 1, upper: 3 }), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_summed_area_table() -> color_eyre::Result<()> {
+        let grid = vec![vec![1u32, 2, 3], vec![4, 5, 6], vec![7, 8, 9]];
+        let table = SummedAreaTable::from_grid(&grid);
+        assert_eq!(table.sum((0, 0).into(), (2, 2).into()), 45);
+        assert_eq!(table.sum((1, 1).into(), (2, 2).into()), 28);
+        assert_eq!(table.sum((0, 2).into(), (1, 2).into()), 9);
+        assert_eq!(table.sum_around((0, 0).into(), 1), 12);
+        let filled = vec![vec![true, false], vec![true, true]];
+        let counts = SummedAreaTable::new(&filled, |cell| *cell as usize);
+        assert_eq!(counts.sum((0, 0).into(), (1, 1).into()), 3);
+        assert_eq!(counts.sum((0, 1).into(), (0, 1).into()), 0);
+        assert_eq!(counts.sum_around((1, 1).into(), usize::MAX), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_summed_area_table() -> color_eyre::Result<()> {
+        let table = SummedAreaTable::from_grid(&Vec::<Vec<u32>>::new());
+        assert_eq!((table.row_count(), table.col_count()), (0, 0));
+        assert_eq!(table.sum_around((0, 0).into(), 1), 0);
+        let no_columns = SummedAreaTable::<u32>::from_grid(&[vec![], vec![]]);
+        assert_eq!(no_columns.sum_around((1, 0).into(), 1), 0);
+        Ok(())
+    }
+}
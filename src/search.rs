@@ -0,0 +1,531 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+use num::Zero;
+
+/// A route to a goal: its total cost, and every state along it from the start to the goal
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Found<S, C> {
+    pub cost: C,
+    pub path: Vec<S>,
+}
+
+/// Breadth first search from `start` to the first state satisfying `is_goal`, where every
+/// move costs 1. The cost is the number of moves.
+pub fn bfs<S, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<Found<S, usize>>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut states = Explored::new(start);
+    let goal = breadth_first(&mut states, successors, is_goal)?;
+    Some(states.found(goal))
+}
+
+/// `bfs`, returning only the number of moves
+pub fn bfs_cost<S, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<usize>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut states = Explored::new(start);
+    let goal = breadth_first(&mut states, successors, is_goal)?;
+    Some(states.cost[goal])
+}
+
+/// Dijkstra's algorithm from `start` to the cheapest state satisfying `is_goal`.
+/// `successors` gives each next state with the cost of moving to it, which mustn't be negative.
+pub fn dijkstra<S, C, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<Found<S, C>>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Zero,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar(start, successors, |_| C::zero(), is_goal)
+}
+
+/// `dijkstra`, returning only the cost
+pub fn dijkstra_cost<S, C, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Zero,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar_cost(start, successors, |_| C::zero(), is_goal)
+}
+
+/// A* search from `start` to the cheapest state satisfying `is_goal`.
+///
+/// `heuristic` estimates the remaining cost to a goal. It must never overestimate,
+/// or the route found may not be the cheapest.
+pub fn astar<S, C, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    heuristic: impl FnMut(&S) -> C,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<Found<S, C>>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Zero,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let mut states = Explored::new(start);
+    let goal = best_first(&mut states, successors, heuristic, is_goal)?;
+    Some(states.found(goal))
+}
+
+/// `astar`, returning only the cost
+pub fn astar_cost<S, C, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    heuristic: impl FnMut(&S) -> C,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Zero,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let mut states = Explored::new(start);
+    let goal = best_first(&mut states, successors, heuristic, is_goal)?;
+    Some(states.cost[goal])
+}
+
+/// How much a search had to keep in memory
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Every distinct state or partial result stored, across all directions
+    pub states_stored: usize,
+    /// The largest number of states waiting to be expanded at once
+    pub peak_frontier: usize,
+}
+
+/// Breadth first search from both `start` and `goal` at once, meeting in the middle.
+///
+/// `predecessors` gives the states that can move to a state in one step, so moves must be
+/// invertible. Each search only needs to go half as deep, so for a branching factor `b`
+/// this holds around `2 * b^(d/2)` states rather than `b^d`.
+pub fn bidirectional_bfs<S, I, J>(
+    start: S,
+    goal: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut predecessors: impl FnMut(&S) -> J,
+) -> (Option<Found<S, usize>>, SearchStats)
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+    J: IntoIterator<Item = S>,
+{
+    let mut forward = Layers::new(start);
+    let mut backward = Layers::new(goal);
+    let mut stats = SearchStats::default();
+    let mut meeting = forward.meeting(&backward);
+    while meeting.is_none() && !forward.frontier.is_empty() && !backward.frontier.is_empty() {
+        stats.peak_frontier = stats
+            .peak_frontier
+            .max(forward.frontier.len() + backward.frontier.len());
+        // Always grow the smaller side, which keeps the two halves balanced
+        meeting = if forward.frontier.len() <= backward.frontier.len() {
+            forward.expand(&mut successors, &backward)
+        } else {
+            backward
+                .expand(&mut predecessors, &forward)
+                .map(|(b, f)| (f, b))
+        };
+    }
+    stats.states_stored = forward.states.len() + backward.states.len();
+    let found = meeting.map(|(f, b)| {
+        let mut path = forward.path_to(f);
+        path.reverse();
+        // The meeting state is the last on the forward path and the first on the backward one
+        path.extend(backward.path_to(b).into_iter().skip(1));
+        Found {
+            cost: path.len() - 1,
+            path,
+        }
+    });
+    (found, stats)
+}
+
+/// Find the fewest `items` which, each used at most once, combine to a target.
+///
+/// `combine` adds an item to a running total starting at `empty`, and must give the same
+/// result whatever order items are added in. `remainder` takes the total of some items and
+/// gives the total the other items must make for the whole to hit the target, or `None`
+/// if that's impossible. For XOR toggles, `combine` and `remainder` are both XOR.
+///
+/// Each half of the items is enumerated separately, so this takes O(2^(n/2)) time and
+/// memory rather than O(2^n). Returns the indices of the chosen items, in order.
+pub fn meet_in_the_middle<T, S>(
+    items: &[T],
+    empty: S,
+    combine: impl Fn(&S, &T) -> S,
+    remainder: impl Fn(&S) -> Option<S>,
+) -> (Option<Vec<usize>>, SearchStats)
+where
+    S: Clone + Eq + Hash,
+{
+    assert!(items.len() <= 64, "too many items to split into halves");
+    let (first, second) = items.split_at(items.len() / 2);
+    let mut best_first: HashMap<S, u64> = HashMap::new();
+    for (total, mask) in subset_totals(first, &empty, &combine) {
+        best_first
+            .entry(total)
+            .and_modify(|best| {
+                if mask.count_ones() < best.count_ones() {
+                    *best = mask;
+                }
+            })
+            .or_insert(mask);
+    }
+    let mut best: Option<u64> = None;
+    for (total, mask) in subset_totals(second, &empty, &combine) {
+        let Some(first_mask) = remainder(&total).and_then(|needed| best_first.get(&needed)) else {
+            continue;
+        };
+        let chosen = first_mask | (mask << first.len());
+        if best.is_none_or(|b| chosen.count_ones() < b.count_ones()) {
+            best = Some(chosen);
+        }
+    }
+    let stats = SearchStats {
+        states_stored: best_first.len(),
+        peak_frontier: 0,
+    };
+    let chosen = best.map(|mask| (0..items.len()).filter(|i| mask & (1 << i) != 0).collect());
+    (chosen, stats)
+}
+
+// Every state seen so far, numbered in the order they were found, with the best known
+// cost and predecessor of each. Only indices go on the queues, so states are cloned once.
+struct Explored<S, C> {
+    indices: HashMap<S, usize>,
+    states: Vec<S>,
+    cost: Vec<C>,
+    parent: Vec<usize>,
+}
+
+impl<S: Clone + Eq + Hash, C: Copy + Zero> Explored<S, C> {
+    fn new(start: S) -> Explored<S, C> {
+        Explored {
+            indices: HashMap::from([(start.clone(), 0)]),
+            states: vec![start],
+            cost: vec![C::zero()],
+            parent: vec![0],
+        }
+    }
+
+    // Record reaching `state` from `parent` with `cost`, if that's the first or cheapest way.
+    // Returns the state's index when it's an improvement.
+    fn relax(&mut self, state: S, parent: usize, cost: C) -> Option<usize>
+    where
+        C: Ord,
+    {
+        match self.indices.entry(state) {
+            Entry::Occupied(entry) => {
+                let index = *entry.get();
+                if cost < self.cost[index] {
+                    self.cost[index] = cost;
+                    self.parent[index] = parent;
+                    Some(index)
+                } else {
+                    None
+                }
+            }
+            Entry::Vacant(entry) => {
+                let index = self.states.len();
+                self.states.push(entry.key().clone());
+                entry.insert(index);
+                self.cost.push(cost);
+                self.parent.push(parent);
+                Some(index)
+            }
+        }
+    }
+
+    fn found(self, goal: usize) -> Found<S, C> {
+        let mut indices = vec![goal];
+        let mut current = goal;
+        while current != 0 {
+            current = self.parent[current];
+            indices.push(current);
+        }
+        let path = indices
+            .into_iter()
+            .rev()
+            .map(|index| self.states[index].clone())
+            .collect();
+        Found {
+            cost: self.cost[goal],
+            path,
+        }
+    }
+}
+
+fn breadth_first<S, I>(
+    states: &mut Explored<S, usize>,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<usize>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut queue = VecDeque::from([0]);
+    while let Some(current) = queue.pop_front() {
+        if is_goal(&states.states[current]) {
+            return Some(current);
+        }
+        let cost = states.cost[current] + 1;
+        for next in successors(&states.states[current]) {
+            // The first time a state is reached is always the shortest, so never revisit
+            if states.indices.contains_key(&next) {
+                continue;
+            }
+            if let Some(index) = states.relax(next, current, cost) {
+                queue.push_back(index);
+            }
+        }
+    }
+    None
+}
+
+fn best_first<S, C, I>(
+    states: &mut Explored<S, C>,
+    mut successors: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> C,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Option<usize>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Zero,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let mut queue = BinaryHeap::from([Reverse((heuristic(&states.states[0]), C::zero(), 0))]);
+    while let Some(Reverse((_, cost, current))) = queue.pop() {
+        if cost > states.cost[current] {
+            // A cheaper route to this state has been found since this was queued
+            continue;
+        }
+        if is_goal(&states.states[current]) {
+            return Some(current);
+        }
+        for (next, step) in successors(&states.states[current]) {
+            let next_cost = cost + step;
+            if let Some(index) = states.relax(next, current, next_cost) {
+                let estimate = next_cost + heuristic(&states.states[index]);
+                queue.push(Reverse((estimate, next_cost, index)));
+            }
+        }
+    }
+    None
+}
+
+// One direction of a bidirectional search, expanded a whole layer at a time
+struct Layers<S> {
+    indices: HashMap<S, usize>,
+    states: Vec<S>,
+    parent: Vec<usize>,
+    frontier: Vec<usize>,
+}
+
+impl<S: Clone + Eq + Hash> Layers<S> {
+    fn new(root: S) -> Layers<S> {
+        Layers {
+            indices: HashMap::from([(root.clone(), 0)]),
+            states: vec![root],
+            parent: vec![0],
+            frontier: vec![0],
+        }
+    }
+
+    // Whether the roots are already the same state
+    fn meeting(&self, other: &Layers<S>) -> Option<(usize, usize)> {
+        other.indices.get(&self.states[0]).map(|o| (0, *o))
+    }
+
+    // Expand the whole frontier by one step. Every state in the new layer is the same
+    // distance away, so the first layer to touch the other side gives a shortest path.
+    fn expand<I>(
+        &mut self,
+        neighbours: &mut impl FnMut(&S) -> I,
+        other: &Layers<S>,
+    ) -> Option<(usize, usize)>
+    where
+        I: IntoIterator<Item = S>,
+    {
+        let mut next_frontier = vec![];
+        let mut meeting = None;
+        for current in std::mem::take(&mut self.frontier) {
+            for next in neighbours(&self.states[current]) {
+                if self.indices.contains_key(&next) {
+                    continue;
+                }
+                let index = self.states.len();
+                if meeting.is_none() {
+                    meeting = other.indices.get(&next).map(|o| (index, *o));
+                }
+                self.indices.insert(next.clone(), index);
+                self.states.push(next);
+                self.parent.push(current);
+                next_frontier.push(index);
+            }
+        }
+        self.frontier = next_frontier;
+        meeting
+    }
+
+    // States from `index` back to the root
+    fn path_to(&self, index: usize) -> Vec<S> {
+        let mut path = vec![self.states[index].clone()];
+        let mut current = index;
+        while current != 0 {
+            current = self.parent[current];
+            path.push(self.states[current].clone());
+        }
+        path
+    }
+}
+
+// The total of every subset of `items`, with a bitmask of which items are in it
+fn subset_totals<T, S: Clone>(
+    items: &[T],
+    empty: &S,
+    combine: impl Fn(&S, &T) -> S,
+) -> Vec<(S, u64)> {
+    let mut totals = vec![(empty.clone(), 0u64)];
+    for (i, item) in items.iter().enumerate() {
+        for j in 0..totals.len() {
+            let (total, mask) = &totals[j];
+            let extended = (combine(total, item), mask | (1 << i));
+            totals.push(extended);
+        }
+    }
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Walk a number line from 0, where each move adds 1, adds 3 or doubles
+    fn moves(n: &i64) -> Vec<(i64, u32)> {
+        vec![(n + 1, 1), (n + 3, 2), (n * 2, 5)]
+            .into_iter()
+            .filter(|(next, _)| *next <= 100)
+            .collect()
+    }
+
+    #[test]
+    fn test_bfs() -> color_eyre::Result<()> {
+        // 0 -> 1 -> 2 -> 5 -> 10
+        let found = bfs(
+            0,
+            |n| moves(n).into_iter().map(|(next, _)| next),
+            |n| *n == 10,
+        );
+        let found = found.unwrap();
+        assert_eq!(found.cost, 4);
+        assert_eq!(found.path.len(), 5);
+        assert_eq!(found.path.first(), Some(&0));
+        assert_eq!(found.path.last(), Some(&10));
+        assert_eq!(bfs_cost(0, |_| vec![], |n| *n == 1), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dijkstra() -> color_eyre::Result<()> {
+        // 0 -> 3 -> 6 -> 9 costs 6, cheaper than any route through doubling
+        let found = dijkstra(0, moves, |n| *n == 9).unwrap();
+        assert_eq!(found.cost, 6);
+        assert_eq!(found.path, vec![0, 3, 6, 9]);
+        assert_eq!(dijkstra_cost(0, moves, |n| *n == 97), Some(24));
+        assert_eq!(dijkstra_cost(0, moves, |n| *n == 101), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_astar() -> color_eyre::Result<()> {
+        // Without doubling every step costs at least 2/3 per unit, so this never overestimates
+        let steps = |n: &i64| vec![(n + 1, 1), (n + 3, 2), (n - 1, 1)];
+        let heuristic = |n: &i64| (((50 - n).max(0) * 2) / 3) as u32;
+        assert_eq!(astar_cost(0, steps, heuristic, |n| *n == 50), Some(34));
+        let found = astar(0, steps, heuristic, |n| *n == 50).unwrap();
+        assert_eq!(found.cost, 34);
+        assert_eq!(found.path.len(), 19);
+        assert_eq!(found.path.last(), Some(&50));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bidirectional_bfs() -> color_eyre::Result<()> {
+        // Moves of +1, +3 and *2, and their inverses
+        let forward = |n: &i64| {
+            moves(n)
+                .into_iter()
+                .map(|(next, _)| next)
+                .collect::<Vec<_>>()
+        };
+        let backward = |n: &i64| {
+            let mut previous = vec![n - 1, n - 3];
+            if n % 2 == 0 {
+                previous.push(n / 2);
+            }
+            previous.into_iter().filter(|p| *p >= 0).collect::<Vec<_>>()
+        };
+        for goal in [0, 10, 37, 97] {
+            let (found, stats) = bidirectional_bfs(0, goal, forward, backward);
+            let found = found.unwrap();
+            assert_eq!(Some(found.cost), bfs_cost(0, forward, |n| *n == goal));
+            assert_eq!(found.path.first(), Some(&0));
+            assert_eq!(found.path.last(), Some(&goal));
+            assert!(
+                found
+                    .path
+                    .windows(2)
+                    .all(|w| forward(&w[0]).contains(&w[1]))
+            );
+            assert!(stats.states_stored > 0);
+        }
+        let (found, _) = bidirectional_bfs(0, -5, forward, backward);
+        assert_eq!(found, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_meet_in_the_middle() -> color_eyre::Result<()> {
+        // Find the fewest masks that XOR to the target
+        let masks = [0b0011u32, 0b0110, 0b1100, 0b1000, 0b0101, 0b1111];
+        let target = 0b1010;
+        let (chosen, stats) = meet_in_the_middle(&masks, 0, |t, m| t ^ m, |t| Some(t ^ target));
+        let chosen = chosen.unwrap();
+        assert_eq!(chosen.len(), 2);
+        assert_eq!(chosen.iter().fold(0, |t, i| t ^ masks[*i]), target);
+        assert_eq!(stats.states_stored, 8);
+        // Sums can't go below the target, so some remainders are impossible
+        let (chosen, _) =
+            meet_in_the_middle(&[5, 3, 9, 4], 0, |t, n| t + n, |t| 12u32.checked_sub(*t));
+        assert_eq!(chosen, Some(vec![1, 2]));
+        let (chosen, _) = meet_in_the_middle(&[5, 3], 0, |t, n| t + n, |t| 1u32.checked_sub(*t));
+        assert_eq!(chosen, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+use itertools::Itertools;
+
+use crate::metric::squared_euclidean;
+use crate::union_find::UnionFind;
+
+/// An edge between two points, identified by their indices in the point slice
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Edge<D> {
+    pub a: usize,
+    pub b: usize,
+    pub distance: D,
+}
+
+/// The result of joining points with edges: the edges used, and the components they form
+#[derive(Clone, Debug)]
+pub struct Connectivity<D> {
+    pub edges: Vec<Edge<D>>,
+    pub components: UnionFind,
+}
+
+/// Every pair of points, closest first. Equal distances are ordered by index so the
+/// result doesn't depend on the sort algorithm. `None` if the metric can't measure some
+/// pair, e.g. because the distance overflows.
+pub fn sorted_pairs<P, D, F>(points: &[P], metric: F) -> Option<Vec<Edge<D>>>
+where
+    D: Ord,
+    F: Fn(&P, &P) -> Option<D>,
+{
+    let edges = (0..points.len())
+        .tuple_combinations()
+        .map(|(a, b)| {
+            Some(Edge {
+                a,
+                b,
+                distance: metric(&points[a], &points[b])?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(
+        edges
+            .into_iter()
+            .sorted_by(|x, y| {
+                x.distance
+                    .cmp(&y.distance)
+                    .then((x.a, x.b).cmp(&(y.a, y.b)))
+            })
+            .collect_vec(),
+    )
+}
+
+/// Join the `count` closest pairs of points, whether or not they were already connected.
+/// Every pair considered is returned in `edges`, closest first. `None` if the metric
+/// fails, as for `sorted_pairs`.
+pub fn connect_closest<P, D, F>(points: &[P], count: usize, metric: F) -> Option<Connectivity<D>>
+where
+    D: Ord,
+    F: Fn(&P, &P) -> Option<D>,
+{
+    let mut components = UnionFind::new(points.len());
+    let edges = sorted_pairs(points, metric)?
+        .into_iter()
+        .take(count)
+        .inspect(|edge| {
+            components.union(edge.a, edge.b);
+        })
+        .collect_vec();
+    Some(Connectivity { edges, components })
+}
+
+/// Kruskal's algorithm over every pair of points. The edges are in the order they were
+/// added, so the last one is the edge that finally connects everything. `None` if the
+/// metric fails, as for `sorted_pairs`.
+pub fn minimum_spanning_tree<P, D, F>(points: &[P], metric: F) -> Option<Vec<Edge<D>>>
+where
+    D: Ord,
+    F: Fn(&P, &P) -> Option<D>,
+{
+    let mut components = UnionFind::new(points.len());
+    let mut tree = vec![];
+    for edge in sorted_pairs(points, metric)? {
+        if components.component_count() <= 1 {
+            break;
+        }
+        if components.union(edge.a, edge.b) {
+            tree.push(edge);
+        }
+    }
+    Some(tree)
+}
+
+/// `connect_closest` using the exact squared Euclidean distance between points, or `None`
+/// if some distance overflows
+pub fn connect_closest_points<const K: usize>(
+    points: &[[i64; K]],
+    count: usize,
+) -> Option<Connectivity<u128>> {
+    connect_closest(points, count, squared_euclidean)
+}
+
+/// `minimum_spanning_tree` using the exact squared Euclidean distance between points, or
+/// `None` if some distance overflows
+pub fn minimum_spanning_tree_points<const K: usize>(
+    points: &[[i64; K]],
+) -> Option<Vec<Edge<u128>>> {
+    minimum_spanning_tree(points, squared_euclidean)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num::abs;
+
+    fn distance(a: &i32, b: &i32) -> Option<i32> {
+        Some(abs(a - b))
+    }
+
+    #[test]
+    fn test_connect_closest() -> color_eyre::Result<()> {
+        let points = [0, 10, 1, 12, 30];
+        let connected = connect_closest(&points, 3, distance).unwrap();
+        assert_eq!(
+            connected
+                .edges
+                .iter()
+                .map(|e| (e.a, e.b, e.distance))
+                .collect_vec(),
+            vec![(0, 2, 1), (1, 3, 2), (1, 2, 9)]
+        );
+        assert_eq!(
+            connected.components.components().collect_vec(),
+            vec![vec![0, 1, 2, 3], vec![4]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_minimum_spanning_tree() -> color_eyre::Result<()> {
+        let points = [0, 10, 1, 12, 30];
+        let tree = minimum_spanning_tree(&points, distance).unwrap();
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.iter().map(|e| e.distance).sum::<i32>(), 30);
+        assert_eq!(tree.last().map(|e| (e.a, e.b)), Some((3, 4)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_points_use_exact_distance() -> color_eyre::Result<()> {
+        // Rounded square roots would make the first two pairs tie at 3
+        let points = [[0, 0, 0], [3, 1, 1], [-3, 0, 0], [100, 0, 0]];
+        let connected = connect_closest_points(&points, 1).unwrap();
+        assert_eq!((connected.edges[0].a, connected.edges[0].b), (0, 2));
+        let tree = minimum_spanning_tree_points(&points).unwrap();
+        assert_eq!(
+            tree.last().map(|e| (e.a, e.b, e.distance)),
+            Some((1, 3, 9411))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_points_overflow() -> color_eyre::Result<()> {
+        let points = [[i64::MIN, i64::MIN], [0, 0], [i64::MAX, i64::MAX]];
+        assert!(connect_closest_points(&points, 1).is_none());
+        assert!(minimum_spanning_tree_points(&points).is_none());
+        Ok(())
+    }
+}
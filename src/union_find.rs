@@ -0,0 +1,141 @@
+use std::mem::swap;
+
+/// Disjoint sets over the elements `0..len`, with path compression and union by size
+/// so that any sequence of operations runs in near-linear time.
+#[derive(Debug, Clone)]
+pub struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    component_count: usize,
+}
+
+impl UnionFind {
+    /// Start with every element in a component of its own
+    pub fn new(len: usize) -> UnionFind {
+        UnionFind {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+            component_count: len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// Find the representative element of `x`'s component
+    pub fn find(&mut self, x: usize) -> usize {
+        let root = self.root(x);
+        // Point everything on the way at the root, so the next lookup is direct
+        let mut current = x;
+        while self.parent[current] != root {
+            let next = self.parent[current];
+            self.parent[current] = root;
+            current = next;
+        }
+        root
+    }
+
+    /// Merge the components containing `a` and `b`.
+    /// Returns false if they were already in the same component.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let mut a = self.find(a);
+        let mut b = self.find(b);
+        if a == b {
+            return false;
+        }
+        // Hang the smaller tree off the larger one to keep the trees shallow
+        if self.size[a] < self.size[b] {
+            swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        self.component_count -= 1;
+        true
+    }
+
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// The number of elements in the same component as `x`, including `x`
+    pub fn component_size(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.size[root]
+    }
+
+    pub fn component_count(&self) -> usize {
+        self.component_count
+    }
+
+    /// The size of every component, in no particular order
+    pub fn component_sizes(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len())
+            .filter(|x| self.parent[*x] == *x)
+            .map(|root| self.size[root])
+    }
+
+    /// The elements of every component. Each component is in ascending order,
+    /// and the components are ordered by their smallest element.
+    pub fn components(&self) -> impl Iterator<Item = Vec<usize>> {
+        let mut components = vec![vec![]; self.len()];
+        for x in 0..self.len() {
+            components[self.root(x)].push(x);
+        }
+        let mut components = components
+            .into_iter()
+            .filter(|c| !c.is_empty())
+            .collect::<Vec<_>>();
+        components.sort_unstable_by_key(|c| c[0]);
+        components.into_iter()
+    }
+
+    // Find without compressing, for when we only have a shared reference
+    fn root(&self, x: usize) -> usize {
+        let mut current = x;
+        while self.parent[current] != current {
+            current = self.parent[current];
+        }
+        current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    #[test]
+    fn test_union() -> color_eyre::Result<()> {
+        let mut sets = UnionFind::new(6);
+        assert_eq!(sets.component_count(), 6);
+        assert!(sets.union(0, 1));
+        assert!(sets.union(2, 1));
+        assert!(!sets.union(0, 2));
+        assert!(sets.union(4, 5));
+        assert!(sets.connected(0, 2));
+        assert!(!sets.connected(0, 3));
+        assert_eq!(sets.component_count(), 3);
+        assert_eq!(sets.component_size(2), 3);
+        assert_eq!(sets.component_size(3), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_components() -> color_eyre::Result<()> {
+        let mut sets = UnionFind::new(5);
+        sets.union(4, 1);
+        sets.union(3, 0);
+        sets.union(0, 4);
+        assert_eq!(
+            sets.components().collect_vec(),
+            vec![vec![0, 1, 3, 4], vec![2]]
+        );
+        assert_eq!(sets.component_sizes().sorted().collect_vec(), vec![1, 4]);
+        Ok(())
+    }
+}